@@ -27,6 +27,16 @@ mod tests {
         });
     }
 
+    #[bench]
+    fn bench_city_path(b: &mut Bencher) {
+        let buffer = std::fs::read("./testdata/GeoIP2-City.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let ip = IpAddr::from_str("81.2.69.142").unwrap();
+        b.iter(|| {
+            reader.lookup_path(ip, &["country", "iso_code"]).unwrap();
+        });
+    }
+
     #[bench]
     fn bench_country_oschwald(b: &mut Bencher) {
         let reader = maxminddb::Reader::open_readfile("./testdata/GeoIP2-Country.mmdb").unwrap();
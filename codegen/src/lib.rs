@@ -1,250 +1,411 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::{
     parse::{Parse, ParseStream},
     parse_macro_input,
     punctuated::Punctuated,
-    DeriveInput, Fields, FieldsNamed, GenericArgument, Ident, ItemStruct, LitStr, PathArguments,
-    Result, Token, Type,
+    spanned::Spanned,
+    DeriveInput, Fields, GenericArgument, Ident, ItemStruct, LitStr, PathArguments, Result, Token,
+    Type,
 };
 
-fn extract_field(field_ident: Ident, ty: &Type) -> proc_macro2::TokenStream {
-    match &ty {
-        syn::Type::Path(tp) => {
-            let segment = &tp.path.segments[0];
-            let ident = &segment.ident;
-            match ident.to_string().as_str() {
-                "Option" => match &segment.arguments {
-                    PathArguments::AngleBracketed(ga) => match &ga.args[0] {
-                        GenericArgument::Type(ty) => match ty {
-                            syn::Type::Path(tp) => {
-                                let segment = &tp.path.segments[0];
-                                let ident = &segment.ident;
-                                match ident.to_string().as_str() {
-                                    "u64" => quote! {
-                                        self.#field_ident = Some(read_usize(buffer, offset)? as u64)
-                                    },
-                                    "u32" => quote! {
-                                        self.#field_ident = Some(read_usize(buffer, offset)? as u32)
-                                    },
-                                    "u16" => quote! {
-                                        self.#field_ident = Some(read_usize(buffer, offset)? as u16)
-                                    },
-                                    "f64" => quote! {
-                                        self.#field_ident = Some(read_f64(buffer, offset)?)
-                                    },
-                                    "bool" => quote! {
-                                        self.#field_ident = Some(read_bool(buffer, offset)?)
-                                    },
-                                    "Map" => quote! {
-                                        self.#field_ident = Some(read_map(buffer, offset)?)
-                                    },
-                                    "models" => {
-                                        let ident = &tp.path.segments[1].ident;
-                                        quote! {
-                                            let mut model = models::#ident::default();
-                                            model.from_bytes(buffer, offset)?;
-                                            self.#field_ident = Some(model);
-                                        }
-                                    }
-                                    "Vec" => match &segment.arguments {
-                                        PathArguments::AngleBracketed(ga) => match &ga.args[0] {
-                                            GenericArgument::Type(syn::Type::Path(p)) => {
-                                                let segment = &p.path.segments[0];
-                                                let ident = &segment.ident;
-                                                match ident.to_string().as_str() {
-                                                    "models" => {
-                                                        let ident = &p.path.segments[1].ident;
-                                                        quote! {
-                                                            let (data_type, size) = read_control(buffer, offset)?;
-                                                            self.#field_ident = Some(match data_type {
-                                                                DATA_TYPE_SLICE => {
-                                                                    let mut array: Vec<models::#ident<'a>> = Vec::with_capacity(size);
-                                                                    for _i in 0..size {
-                                                                        let mut model = models::#ident::default();
-                                                                        model.from_bytes(buffer, offset)?;
-                                                                        array.push(model);
-                                                                    }
-                                                                    array
-                                                                }
-                                                                DATA_TYPE_POINTER => {
-                                                                    let offset = &mut read_pointer(buffer, offset, size)?;
-                                                                    let (data_type, size) = read_control(buffer, offset)?;
-                                                                    match data_type {
-                                                                        DATA_TYPE_SLICE => {
-                                                                            let mut array: Vec<models::#ident<'a>> =
-                                                                                Vec::with_capacity(size);
-                                                                            for _ in 0..size {
-                                                                                let mut model = models::#ident::default();
-                                                                                model.from_bytes(buffer, offset)?;
-                                                                                array.push(model);
-                                                                            }
-                                                                            array
-                                                                        }
-                                                                        _ => return Err(Error::InvalidDataType(data_type)),
-                                                                    }
-                                                                }
-                                                                _ => return Err(Error::InvalidDataType(data_type)),
-                                                            })
-                                                        }
-                                                    }
-                                                    _ => unimplemented!(),
-                                                }
-                                            }
-                                            _ => unimplemented!("{:?}", &ga.args[0]),
-                                        },
-                                        _ => unimplemented!("{:?}", &segment.arguments),
-                                    },
-                                    _ => unimplemented!("{:?}", ident),
-                                }
-                            }
-                            syn::Type::Reference(tr) => match tr.elem.as_ref() {
-                                syn::Type::Path(tp) => {
-                                    let segment = &tp.path.segments[0];
-                                    let ident = &segment.ident;
-                                    match ident.to_string().as_str() {
-                                        "str" => quote! {
-                                            self.#field_ident = Some(read_str(buffer, offset)?)
-                                        },
-                                        _ => unimplemented!("{:?}", ident),
-                                    }
-                                }
-                                _ => unimplemented!("{:?}", tr.elem),
-                            },
-                            _ => unimplemented!("{:?}", ty),
-                        },
-                        _ => unimplemented!("{:?}", &ga.args[0]),
-                    },
-                    _ => unimplemented!("{:?}", &segment.arguments),
-                },
-                "Vec" => match &segment.arguments {
-                    PathArguments::AngleBracketed(ga) => match &ga.args[0] {
-                        GenericArgument::Type(ty) => match ty {
-                            syn::Type::Reference(tr) => match tr.elem.as_ref() {
-                                syn::Type::Path(tp) => {
-                                    let segment = &tp.path.segments[0];
-                                    let ident = &segment.ident;
-                                    match ident.to_string().as_str() {
-                                        "str" => quote! {
-                                            self.#field_ident = read_array(buffer, offset)?
-                                        },
-                                        _ => unimplemented!("{:?}", ident),
-                                    }
-                                }
-                                _ => unimplemented!("{:?}", tr.elem),
-                            },
-                            _ => unimplemented!("{:?}", ty),
-                        },
-                        _ => unimplemented!("{:?}", &ga.args[0]),
-                    },
-                    _ => unimplemented!("{:?}", &segment.arguments),
-                },
-                "u64" => quote! {
-                    self.#field_ident = read_usize(buffer, offset)? as u64
-                },
-                "u32" => quote! {
-                    self.#field_ident = read_usize(buffer, offset)? as u32
-                },
-                "u16" => quote! {
-                    self.#field_ident = read_usize(buffer, offset)? as u16
-                },
-                "Map" => quote! {
-                    self.#field_ident = read_map(buffer, offset)?
-                },
-                _ => unimplemented!("{:?}", ident),
-            }
-        }
-        syn::Type::Reference(tr) => match tr.elem.as_ref() {
-            syn::Type::Path(tp) => {
-                let segment = &tp.path.segments[0];
-                let ident = &segment.ident;
+const NAMED_FIELDS_ONLY: &str = "only structs with named fields can be decoded";
+
+/// Returns the single type argument of `ty` if it is `wrapper<T>`, like `Option` or `Vec`.
+fn type_argument<'t>(ty: &'t Type, wrapper: &str) -> Option<&'t Type> {
+    let segment = match ty {
+        Type::Path(tp) if tp.qself.is_none() => tp.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(ga) if ga.args.len() == 1 => match &ga.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns an expression reading a value of type `ty` from `buffer` at `offset`,
+/// evaluating to a `Result`.
+fn read_value(ty: &Type) -> Result<proc_macro2::TokenStream> {
+    if let Some(ty) = type_argument(ty, "Vec") {
+        let read = read_value(ty)?;
+        return Ok(quote! {
+            read_vec(buffer, offset, |buffer, offset| #read)
+        });
+    }
+    match ty {
+        Type::Path(tp) if tp.qself.is_none() => {
+            if let Some(ident) = tp.path.get_ident() {
                 match ident.to_string().as_str() {
-                    "str" => quote! {
-                        self.#field_ident = read_str(buffer, offset)?
-                    },
-                    _ => unimplemented!("{:?}", ident),
+                    "u128" => return Ok(quote! { read_u128(buffer, offset) }),
+                    "u64" => return Ok(quote! { read_u64(buffer, offset) }),
+                    "u32" => return Ok(quote! { read_u32(buffer, offset) }),
+                    "u16" => return Ok(quote! { read_u16(buffer, offset) }),
+                    "i32" => return Ok(quote! { read_i32(buffer, offset) }),
+                    "f64" => return Ok(quote! { read_f64(buffer, offset) }),
+                    "f32" => return Ok(quote! { read_f32(buffer, offset) }),
+                    "bool" => return Ok(quote! { read_bool(buffer, offset) }),
+                    "String" => {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "String can't be decoded, use &str instead",
+                        ))
+                    }
+                    "u8" | "usize" | "i8" | "i16" | "i64" | "i128" | "isize" | "char" => {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            format!(
+                                "{} can't be decoded, use one of u16, u32, u64, u128, i32, f32, f64 or bool",
+                                ident
+                            ),
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+            let last = tp.path.segments.last().unwrap();
+            if last.ident == "Map" {
+                return Ok(quote! { read_map(buffer, offset) });
+            }
+            if last.ident == "Option" {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "Option is only supported as the type of a field",
+                ));
+            }
+            // Spanned so a type not implementing `Decoder` is reported on the field.
+            Ok(quote_spanned! {ty.span()=>
+                {
+                    let mut value = <#ty as ::std::default::Default>::default();
+                    ::geoip2::Decoder::decode(&mut value, buffer, offset).map(|()| value)
                 }
+            })
+        }
+        Type::Reference(tr) => match tr.elem.as_ref() {
+            Type::Path(tp) if tp.path.is_ident("str") => Ok(quote! { read_str(buffer, offset) }),
+            Type::Slice(ts) if matches!(ts.elem.as_ref(), Type::Path(tp) if tp.path.is_ident("u8")) => {
+                Ok(quote! { read_binary(buffer, offset) })
             }
-            _ => unimplemented!("{:?}", tr.elem),
+            _ => Err(syn::Error::new_spanned(
+                ty,
+                "only &str and &[u8] references can be decoded",
+            )),
         },
-        _ => unimplemented!("{:?}", ty),
+        _ => Err(syn::Error::new_spanned(ty, "unsupported field type")),
     }
 }
 
-fn extract_fields(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
-    let fields = if let syn::Fields::Named(FieldsNamed { named, .. }) = fields {
-        named
-    } else {
-        unimplemented!("{:?}", fields);
-    };
+fn extract_field(field_ident: Ident, ty: &Type) -> Result<proc_macro2::TokenStream> {
+    Ok(match type_argument(ty, "Option") {
+        Some(ty) => {
+            let read = read_value(ty)?;
+            quote! { self.#field_ident = Some(#read?) }
+        }
+        None => {
+            let read = read_value(ty)?;
+            quote! { self.#field_ident = #read? }
+        }
+    })
+}
+
+fn check_fields(ident: &Ident, fields: &Fields) -> Result<()> {
+    match fields {
+        Fields::Named(_) => Ok(()),
+        Fields::Unnamed(fields) => Err(syn::Error::new_spanned(fields, NAMED_FIELDS_ONLY)),
+        Fields::Unit => Err(syn::Error::new_spanned(ident, NAMED_FIELDS_ONLY)),
+    }
+}
+
+/// Options set with `#[geoip2(...)]` on a field.
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    /// Never decoded nor serialized, the key is treated as unknown.
+    skip: bool,
+    /// Decodes the keys no other field matches into this field's own fields.
+    flatten: bool,
+}
+
+fn field_attrs(field: &syn::Field) -> Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter() {
+        if !attr.path().is_ident("geoip2") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attrs.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                attrs.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("flatten") {
+                attrs.flatten = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported geoip2 attribute"))
+            }
+        })?;
+        if attrs.flatten && (attrs.skip || attrs.rename.is_some()) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "flatten can't be combined with rename or skip",
+            ));
+        }
+    }
+    Ok(attrs)
+}
+
+fn field_key(field: &syn::Field, attrs: &FieldAttrs) -> String {
+    match &attrs.rename {
+        Some(rename) => rename.clone(),
+        None => format!("{}", field.ident.as_ref().unwrap()),
+    }
+}
+
+/// Returns the match arms decoding each field and the flattened fields.
+fn extract_fields(fields: &Fields) -> Result<(Vec<proc_macro2::TokenStream>, Vec<Ident>)> {
+    let mut result = Vec::new();
+    let mut flattened = Vec::new();
+    // Reports every unsupported field at once instead of only the first one.
+    let mut errors: Option<syn::Error> = None;
+    for field in fields.iter() {
+        let field_ident = field.ident.clone().unwrap();
+        let field_stream = field_attrs(field).and_then(|attrs| {
+            if attrs.skip {
+                return Ok(None);
+            }
+            if attrs.flatten {
+                flattened.push(field_ident.clone());
+                return Ok(None);
+            }
+            let field_ident_value = field_key(field, &attrs);
+            let field_stream = extract_field(field_ident, &field.ty)?;
+            Ok(Some(quote! {
+                #field_ident_value => {
+                    #field_stream;
+                    true
+                }
+            }))
+        });
+        match field_stream {
+            Ok(Some(field_stream)) => result.push(field_stream),
+            Ok(None) => {}
+            Err(err) => match &mut errors {
+                Some(errors) => errors.combine(err),
+                None => errors = Some(err),
+            },
+        }
+    }
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok((result, flattened)),
+    }
+}
+
+fn serialize_fields(fields: &Fields) -> Result<Vec<proc_macro2::TokenStream>> {
     let mut result = Vec::new();
     for field in fields.iter() {
         let field_ident = field.ident.clone().unwrap();
-        let mut field_ident_value = format!("{}", field_ident);
-        if field_ident_value == "country_type" {
-            field_ident_value = "type".into();
+        let attrs = field_attrs(field)?;
+        if attrs.skip {
+            continue;
+        }
+        if attrs.flatten {
+            result.push(quote! {
+                ::geoip2::__private::SerializeFields::serialize_fields(&self.#field_ident, map)?;
+            });
+            continue;
         }
-        let field_stream = extract_field(field_ident, &field.ty);
-        result.push(quote! {
-            #field_ident_value => {
-                #field_stream
+        let field_ident_value = field_key(field, &attrs);
+        result.push(if type_argument(&field.ty, "Option").is_some() {
+            quote! {
+                if let Some(value) = &self.#field_ident {
+                    map.serialize_entry(#field_ident_value, value)?;
+                }
+            }
+        } else {
+            quote! {
+                map.serialize_entry(#field_ident_value, &self.#field_ident)?;
             }
         });
     }
-    result
+    Ok(result)
+}
+
+/// The `#[geoip2(...)]` attributes set on the struct itself.
+#[derive(Default)]
+struct ContainerAttrs {
+    /// Implements `Serialize`, which requires the `serde` feature of `geoip2`.
+    serialize: bool,
+    /// Fails decoding on a key that matches no field instead of skipping it.
+    deny_unknown_fields: bool,
+}
+
+fn container_attrs(attrs: &[syn::Attribute]) -> Result<ContainerAttrs> {
+    let mut result = ContainerAttrs::default();
+    for attr in attrs.iter() {
+        if !attr.path().is_ident("geoip2") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("serialize") {
+                result.serialize = true;
+                Ok(())
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                result.deny_unknown_fields = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported geoip2 attribute"))
+            }
+        })?;
+    }
+    Ok(result)
+}
+
+/// Only emitted for structs with `#[geoip2(serialize)]`, so a `Serialize`
+/// derived next to `Decoder` doesn't conflict with it.
+fn serialize_impl(
+    attrs: &ContainerAttrs,
+    ident: &Ident,
+    generics: &syn::Generics,
+    fields: &Fields,
+) -> Result<proc_macro2::TokenStream> {
+    if !attrs.serialize {
+        return Ok(quote! {});
+    }
+    let entries = serialize_fields(fields)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::geoip2::__private::serde::Serialize for #ident #ty_generics #where_clause {
+            fn serialize<S: ::geoip2::__private::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                use ::geoip2::__private::serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(None)?;
+                ::geoip2::__private::SerializeFields::serialize_fields(self, &mut map)?;
+                map.end()
+            }
+        }
+
+        impl #impl_generics ::geoip2::__private::SerializeFields for #ident #ty_generics #where_clause {
+            fn serialize_fields<M: ::geoip2::__private::serde::ser::SerializeMap>(
+                &self,
+                map: &mut M,
+            ) -> ::std::result::Result<(), M::Error> {
+                #(#entries)*
+                Ok(())
+            }
+        }
+    })
+}
+
+fn decoder_impl(
+    attrs: &ContainerAttrs,
+    ident: &Ident,
+    generics: &syn::Generics,
+    fields: &Fields,
+) -> Result<proc_macro2::TokenStream> {
+    let (fields, flattened) = extract_fields(fields)?;
+    // The data section lifetime is the struct's own, or a fresh one for
+    // structs that don't borrow from it.
+    let mut impl_generics = generics.clone();
+    let lifetime = match generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let lifetime = syn::Lifetime::new("'a", proc_macro2::Span::call_site());
+            impl_generics
+                .params
+                .insert(0, syn::LifetimeParam::new(lifetime.clone()).into());
+            lifetime
+        }
+    };
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let deny_unknown_fields = if attrs.deny_unknown_fields {
+        quote! {
+            fn deny_unknown_fields(&self) -> bool {
+                true
+            }
+        }
+    } else {
+        quote! {}
+    };
+    Ok(quote! {
+        impl #impl_generics ::geoip2::Decoder<#lifetime> for #ident #ty_generics #where_clause {
+            fn decode_field(
+                &mut self,
+                key: &str,
+                buffer: &#lifetime [u8],
+                offset: &mut usize,
+            ) -> ::std::result::Result<bool, ::geoip2::Error> {
+                #[allow(unused_imports)]
+                use ::geoip2::__private::*;
+                Ok(match key {
+                    #(#fields ,)*
+                    _ => #(::geoip2::Decoder::decode_field(&mut self.#flattened, key, buffer, offset)? ||)* false,
+                })
+            }
+
+            #deny_unknown_fields
+        }
+    })
+}
+
+/// The `Decoder` impl and, with `#[geoip2(serialize)]`, the `Serialize` one.
+fn impls(
+    attrs: &[syn::Attribute],
+    ident: &Ident,
+    generics: &syn::Generics,
+    fields: &Fields,
+) -> Result<proc_macro2::TokenStream> {
+    check_fields(ident, fields)?;
+    let attrs = container_attrs(attrs)?;
+    let decoder = decoder_impl(&attrs, ident, generics, fields)?;
+    let serialize = serialize_impl(&attrs, ident, generics, fields)?;
+    Ok(quote! {
+        #serialize
+
+        #decoder
+    })
 }
 
-#[proc_macro_derive(Decoder)]
+#[proc_macro_derive(Decoder, attributes(geoip2))]
 pub fn derive_decoder(input: TokenStream) -> TokenStream {
     let DeriveInput {
+        attrs,
         ident,
         generics,
         data,
         ..
     } = parse_macro_input!(input);
 
-    let fields = if let syn::Data::Struct(s) = data {
-        extract_fields(&s.fields)
-    } else {
-        unimplemented!("{:?}", data)
-    };
-
-    let output = quote! {
-        impl<'a> #ident #generics {
-            pub(crate) fn from_bytes(&mut self, buffer: &'a [u8], offset: &mut usize) -> Result<(), Error> {
-                let (data_type, size) = read_control(buffer, offset)?;
-                match data_type {
-                    DATA_TYPE_MAP => self.from_bytes_map(buffer, offset, size),
-                    DATA_TYPE_POINTER => {
-                        let offset = &mut read_pointer(buffer, offset, size)?;
-                        let (data_type, size) = read_control(buffer, offset)?;
-                        match data_type {
-                            DATA_TYPE_MAP => self.from_bytes_map(buffer, offset, size),
-                            _ => return Err(Error::InvalidDataType(data_type)),
-                        }
-                    }
-                    _ => return Err(Error::InvalidDataType(data_type)),
-                }
-            }
-
-            fn from_bytes_map(
-                &mut self,
-                buffer: &'a [u8],
-                offset: &mut usize,
-                size: usize,
-            ) -> Result<(), Error> {
-                for _ in 0..size {
-                    match read_str(buffer, offset)? {
-                        #(#fields ,)*
-                        field => return Err(Error::UnknownField(field.into()))
-                    }
-                }
-                Ok(())
-            }
+    let fields = match data {
+        syn::Data::Struct(s) => s.fields,
+        syn::Data::Enum(e) => {
+            return syn::Error::new_spanned(e.enum_token, NAMED_FIELDS_ONLY)
+                .to_compile_error()
+                .into()
+        }
+        syn::Data::Union(u) => {
+            return syn::Error::new_spanned(u.union_token, NAMED_FIELDS_ONLY)
+                .to_compile_error()
+                .into()
         }
     };
 
-    output.into()
+    let output = impls(&attrs, &ident, &generics, &fields);
+
+    match output {
+        Ok(output) => output.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
 }
 
 struct Args {
@@ -266,55 +427,66 @@ pub fn reader(metadata: TokenStream, input: TokenStream) -> TokenStream {
 
     let types_len = types.len();
 
-    let input = parse_macro_input!(input as ItemStruct);
+    let mut input = parse_macro_input!(input as ItemStruct);
+    let decoder = match impls(&input.attrs, &input.ident, &input.generics, &input.fields) {
+        Ok(decoder) => decoder,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // Unlike a derive, an attribute macro must remove the attributes it handles.
+    input.attrs.retain(|attr| !attr.path().is_ident("geoip2"));
+    for field in input.fields.iter_mut() {
+        field.attrs.retain(|attr| !attr.path().is_ident("geoip2"));
+    }
     let ident = &input.ident;
     let generics = &input.generics;
-    let fields = extract_fields(&input.fields);
+    let ident_of = if generics.lifetimes().next().is_some() {
+        quote! { #ident<'r> }
+    } else {
+        quote! { #ident }
+    };
 
     let output = quote! {
         #input
 
+        #decoder
+
         impl<'a> Reader<'a, #ident #generics> {
             pub fn from_bytes(buffer: &[u8]) -> Result<Reader<#ident>, Error> {
-                const types: [&'static str; #types_len] = [#(#types ,)*];
-                let reader = Reader::from_bytes_raw(buffer)?;
-                if !types.contains(&reader.metadata.database_type) {
-                    return Err(Error::InvalidDatabaseType(
-                        reader.metadata.database_type.into(),
-                    ));
-                }
-                Ok(reader)
+                Reader::from_bytes_checked(buffer)
             }
 
             pub fn lookup(&self, address: IpAddr) -> Result<#ident, Error> {
+                self.lookup_decoded(address)
+            }
+
+            pub fn lookup_network(&self, address: IpAddr) -> Result<(#ident, Network), Error> {
+                let (mut offset, prefix_len) = self.get_offset_with_prefix(address)?;
                 let mut result = #ident::default();
-                result.from_bytes(self.decoder_buffer, &mut self.get_offset(address)?)?;
-                Ok(result)
+                result.decode(self.decoder_buffer, &mut offset)?;
+                Ok((result, self.get_network(address, prefix_len)?))
             }
         }
 
-        impl<'a> #ident #generics {
-            pub(crate) fn from_bytes(&mut self, buffer: &'a [u8], offset: &mut usize) -> Result<(), Error> {
-                let (data_type, size) = read_control(buffer, offset)?;
-                if data_type != DATA_TYPE_MAP {
-                    return Err(Error::InvalidDataType(data_type));
-                }
-                self.from_bytes_map(buffer, offset, size)
+        impl #generics private::Sealed for #ident #generics {}
+
+        impl #generics Record for #ident #generics {
+            type Of<'r> = #ident_of;
+
+            fn is_valid_database_type(database_type: &str) -> bool {
+                const types: [&'static str; #types_len] = [#(#types ,)*];
+                types.contains(&database_type)
             }
+        }
 
-            fn from_bytes_map(
-                &mut self,
-                buffer: &'a [u8],
-                offset: &mut usize,
-                size: usize,
-            ) -> Result<(), Error> {
-                for _ in 0..size {
-                    match read_str(buffer, offset)? {
-                        #(#fields ,)*
-                        field => return Err(Error::UnknownField(field.into()))
-                    }
-                }
-                Ok(())
+        impl<'r, 'a> Iterator for Networks<'r, 'a, #ident #generics> {
+            type Item = Result<(Network, #ident #generics), Error>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                Some(self.next_offset()?.and_then(|(network, mut offset)| {
+                    let mut result = #ident::default();
+                    result.decode(self.reader.decoder_buffer, &mut offset)?;
+                    Ok((network, result))
+                }))
             }
         }
     };
@@ -0,0 +1,25 @@
+#![no_main]
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use geoip2::{City, Reader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let reader = match Reader::<City>::from_bytes(data) {
+        Ok(reader) => reader,
+        Err(_) => return,
+    };
+    for ip in [
+        IpAddr::V4(Ipv4Addr::new(81, 2, 69, 142)),
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(Ipv6Addr::new(0x2001, 0x218, 0, 0, 0, 0, 0, 1)),
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    ] {
+        let _ = reader.lookup(ip);
+        let _ = reader.lookup_value(ip);
+    }
+    for result in reader.networks().take(256) {
+        let _ = result;
+    }
+});
@@ -0,0 +1,171 @@
+use std::io::{self, BufRead, Write};
+use std::net::IpAddr;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::{env, fs};
+
+use geoip2::{Any, Error, Reader};
+use serde_json::json;
+
+const USAGE: &str = "usage: geoip2 <command> <database> [ip...]
+
+commands:
+    lookup <database> [ip...]  print the record of every ip as JSON, the ips
+                               are read from stdin, one per line, if none are given
+    metadata <database>        print the metadata as JSON
+    verify <database>          check the whole database
+    dump <database>            print every network with its record as JSON
+    stats <database>           print statistics about the database as JSON";
+
+/// The commands taking no argument but the database.
+const COMMANDS: [&str; 4] = ["metadata", "verify", "dump", "stats"];
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    // The command is checked before the database is read, which may be large.
+    let (command, path, ips) = match args.as_slice() {
+        [command, path, ips @ ..] if command == "lookup" => (command.as_str(), path, ips),
+        [command, path] if COMMANDS.contains(&command.as_str()) => {
+            (command.as_str(), path, &[][..])
+        }
+        _ => return usage(),
+    };
+    let buffer = match fs::read(path) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            eprintln!("geoip2: {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let reader = match Reader::<Any>::from_bytes(&buffer) {
+        Ok(reader) => reader,
+        Err(err) => {
+            eprintln!("geoip2: {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match command {
+        "lookup" => lookup(&reader, ips),
+        "metadata" => print_pretty(reader.get_metadata()),
+        "verify" => verify(&reader),
+        "dump" => dump(&reader),
+        "stats" => stats(&reader, buffer.len()),
+        _ => unreachable!("unknown command {}", command),
+    };
+    match result {
+        Ok(code) => code,
+        // The output was closed early, e.g. piped into `head`.
+        Err(err) if is_broken_pipe(&*err) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("geoip2: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn is_broken_pipe(err: &(dyn std::error::Error + 'static)) -> bool {
+    let kind = match err.downcast_ref::<serde_json::Error>() {
+        Some(err) => err.io_error_kind(),
+        None => err.downcast_ref::<io::Error>().map(io::Error::kind),
+    };
+    kind == Some(io::ErrorKind::BrokenPipe)
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
+
+fn print_pretty<T: serde::Serialize>(value: &T) -> Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, value)?;
+    writeln!(out)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints one line per ip, failing at the end if any of them was invalid.
+fn lookup(reader: &Reader<Any>, ips: &[String]) -> Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    let mut code = ExitCode::SUCCESS;
+    let mut lookup = |ip: &str| -> Result<()> {
+        let address = match IpAddr::from_str(ip) {
+            Ok(address) => address,
+            Err(err) => {
+                eprintln!("geoip2: {}: {}", ip, err);
+                code = ExitCode::FAILURE;
+                return Ok(());
+            }
+        };
+        let line = match reader.lookup_network(address) {
+            Ok((data, network)) => {
+                json!({ "ip": ip, "network": network.to_string(), "data": data })
+            }
+            Err(Error::NotFound) => json!({ "ip": ip, "network": null, "data": null }),
+            Err(err) => {
+                eprintln!("geoip2: {}: {}", ip, err);
+                code = ExitCode::FAILURE;
+                return Ok(());
+            }
+        };
+        serde_json::to_writer(&mut out, &line)?;
+        writeln!(out)?;
+        Ok(())
+    };
+    if ips.is_empty() {
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                lookup(line.trim())?;
+            }
+        }
+    } else {
+        for ip in ips {
+            lookup(ip)?;
+        }
+    }
+    Ok(code)
+}
+
+fn verify(reader: &Reader<Any>) -> Result<ExitCode> {
+    reader.verify()?;
+    println!("ok");
+    Ok(ExitCode::SUCCESS)
+}
+
+fn dump(reader: &Reader<Any>) -> Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    for result in reader.networks() {
+        let (network, data) = result?;
+        serde_json::to_writer(
+            &mut out,
+            &json!({ "network": network.to_string(), "data": data }),
+        )?;
+        writeln!(out)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn stats(reader: &Reader<Any>, file_size: usize) -> Result<ExitCode> {
+    let (mut ipv4_networks, mut ipv6_networks) = (0usize, 0usize);
+    for result in reader.networks().without_records() {
+        match result?.address() {
+            IpAddr::V4(_) => ipv4_networks += 1,
+            IpAddr::V6(_) => ipv6_networks += 1,
+        }
+    }
+    let metadata = reader.get_metadata();
+    print_pretty(&json!({
+        "database_type": metadata.database_type,
+        "ip_version": metadata.ip_version,
+        "record_size": metadata.record_size,
+        "node_count": metadata.node_count,
+        "build_epoch": metadata.build_epoch,
+        "languages": metadata.languages,
+        "file_size": file_size,
+        "networks": ipv4_networks + ipv6_networks,
+        "ipv4_networks": ipv4_networks,
+        "ipv6_networks": ipv6_networks,
+    }))
+}
@@ -1,10 +1,15 @@
+use std::collections::HashSet;
+
 use crate::errors::Error;
 
+#[cfg(feature = "serde")]
+pub(crate) mod de;
+
 pub(crate) const DATA_TYPE_EXTENDED: u8 = 0;
 pub(crate) const DATA_TYPE_POINTER: u8 = 1;
 pub(crate) const DATA_TYPE_STRING: u8 = 2;
 pub(crate) const DATA_TYPE_FLOAT64: u8 = 3;
-// pub(crate) const DATA_TYPE_BYTES: u8 = 4;
+pub(crate) const DATA_TYPE_BYTES: u8 = 4;
 pub(crate) const DATA_TYPE_UINT16: u8 = 5;
 pub(crate) const DATA_TYPE_UINT32: u8 = 6;
 pub(crate) const DATA_TYPE_MAP: u8 = 7;
@@ -12,35 +17,56 @@ pub(crate) const DATA_TYPE_INT32: u8 = 8;
 pub(crate) const DATA_TYPE_UINT64: u8 = 9;
 pub(crate) const DATA_TYPE_UINT128: u8 = 10;
 pub(crate) const DATA_TYPE_SLICE: u8 = 11;
+// Never found in the data section.
 // pub(crate) const DATA_TYPE_DATA_CACHE_CONTAINER: u8 = 12;
 // pub(crate) const DATA_TYPE_END_MARKER: u8 = 13;
 pub(crate) const DATA_TYPE_BOOL: u8 = 14;
-// pub(crate) const DATA_TYPE_FLOAT32: u8 = 15;
+pub(crate) const DATA_TYPE_FLOAT32: u8 = 15;
 
 pub(crate) fn read_bytes<'a>(
     buffer: &'a [u8],
     offset: &mut usize,
     size: usize,
 ) -> Result<&'a [u8], Error> {
-    let new_offset = *offset + size;
-    if new_offset > buffer.len() {
-        return Err(Error::InvalidOffset);
-    }
+    let new_offset = match offset.checked_add(size) {
+        Some(new_offset) if new_offset <= buffer.len() => new_offset,
+        _ => return Err(Error::InvalidOffset(*offset)),
+    };
     let bytes = &buffer[*offset..new_offset];
     *offset = new_offset;
     Ok(bytes)
 }
 
+fn read_byte(buffer: &[u8], offset: &mut usize) -> Result<u8, Error> {
+    match buffer.get(*offset) {
+        Some(&byte) => {
+            *offset += 1;
+            Ok(byte)
+        }
+        None => Err(Error::InvalidOffset(*offset)),
+    }
+}
+
 pub(crate) fn read_control(buffer: &[u8], offset: &mut usize) -> Result<(u8, usize), Error> {
-    let control_byte = buffer[*offset];
-    *offset += 1;
+    let start = *offset;
+    let control_byte = read_byte(buffer, offset)?;
     let mut data_type = control_byte >> 5;
     if data_type == DATA_TYPE_EXTENDED {
-        data_type = buffer[*offset] + 7;
-        *offset += 1;
+        let extended_type = read_byte(buffer, offset)?;
+        data_type = match extended_type.checked_add(7) {
+            Some(data_type) if extended_type != 0 => data_type,
+            _ => {
+                return Err(Error::InvalidDataType {
+                    expected: "extended type",
+                    found: extended_type,
+                    offset: start,
+                })
+            }
+        };
     }
     let mut size = (control_byte as usize) & 0x1f;
-    if data_type == DATA_TYPE_EXTENDED || size < 29 {
+    // The size bits of a pointer hold the pointer itself.
+    if data_type == DATA_TYPE_POINTER || size < 29 {
         return Ok((data_type, size));
     }
     let bytes_to_read = size - 28;
@@ -68,62 +94,250 @@ pub(crate) fn read_pointer(buffer: &[u8], offset: &mut usize, size: usize) -> Re
     Ok(unpacked + pointer_value_offset)
 }
 
-pub(crate) fn read_usize(buffer: &[u8], offset: &mut usize) -> Result<usize, Error> {
+pub fn read_usize(buffer: &[u8], offset: &mut usize) -> Result<usize, Error> {
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_INT32 | DATA_TYPE_UINT64
+        | DATA_TYPE_UINT128 => bytes_to_checked_usize(read_bytes(buffer, offset, size)?, start),
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            match data_type {
+                DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_INT32 | DATA_TYPE_UINT64
+                | DATA_TYPE_UINT128 => {
+                    bytes_to_checked_usize(read_bytes(buffer, offset, size)?, start)
+                }
+                _ => Err(Error::InvalidDataType {
+                    expected: "integer",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "integer",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+/// Reads an unsigned integer into a `u16` field, failing if it doesn't fit.
+pub fn read_u16(buffer: &[u8], offset: &mut usize) -> Result<u16, Error> {
+    let start = *offset;
+    u16::try_from(read_usize(buffer, offset)?).map_err(|_| Error::IntegerOverflow(start))
+}
+
+/// Reads an unsigned integer into a `u32` field, failing if it doesn't fit.
+pub fn read_u32(buffer: &[u8], offset: &mut usize) -> Result<u32, Error> {
+    let start = *offset;
+    u32::try_from(read_usize(buffer, offset)?).map_err(|_| Error::IntegerOverflow(start))
+}
+
+/// Reads an unsigned integer into a `u64` field, failing if it doesn't fit.
+pub fn read_u64(buffer: &[u8], offset: &mut usize) -> Result<u64, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_INT32 | DATA_TYPE_UINT64
-        | DATA_TYPE_UINT128 => Ok(bytes_to_usize(read_bytes(buffer, offset, size)?)),
+        | DATA_TYPE_UINT128 => bytes_to_u64(read_bytes(buffer, offset, size)?, start),
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
                 DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_INT32 | DATA_TYPE_UINT64
-                | DATA_TYPE_UINT128 => Ok(bytes_to_usize(read_bytes(buffer, offset, size)?)),
-                _ => Err(Error::InvalidDataType(data_type)),
+                | DATA_TYPE_UINT128 => bytes_to_u64(read_bytes(buffer, offset, size)?, start),
+                _ => Err(Error::InvalidDataType {
+                    expected: "integer",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "integer",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+pub fn read_u128(buffer: &[u8], offset: &mut usize) -> Result<u128, Error> {
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_UINT64 | DATA_TYPE_UINT128 => {
+            bytes_to_checked_u128(read_bytes(buffer, offset, size)?, start)
+        }
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            match data_type {
+                DATA_TYPE_UINT16 | DATA_TYPE_UINT32 | DATA_TYPE_UINT64 | DATA_TYPE_UINT128 => {
+                    bytes_to_checked_u128(read_bytes(buffer, offset, size)?, start)
+                }
+                _ => Err(Error::InvalidDataType {
+                    expected: "unsigned integer",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "unsigned integer",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+pub fn read_i32(buffer: &[u8], offset: &mut usize) -> Result<i32, Error> {
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_INT32 => bytes_to_i32(read_bytes(buffer, offset, size)?, start),
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            match data_type {
+                DATA_TYPE_INT32 => bytes_to_i32(read_bytes(buffer, offset, size)?, start),
+                _ => Err(Error::InvalidDataType {
+                    expected: "int32",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "int32",
+            found: data_type,
+            offset: start,
+        }),
     }
 }
 
-pub(crate) fn read_bool(buffer: &[u8], offset: &mut usize) -> Result<bool, Error> {
+pub fn read_bool(buffer: &[u8], offset: &mut usize) -> Result<bool, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_BOOL => Ok(size != 0),
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
                 DATA_TYPE_BOOL => Ok(size != 0),
-                _ => Err(Error::InvalidDataType(data_type)),
+                _ => Err(Error::InvalidDataType {
+                    expected: "boolean",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "boolean",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+pub fn read_f64(buffer: &[u8], offset: &mut usize) -> Result<f64, Error> {
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_FLOAT64 => bytes_to_f64(read_bytes(buffer, offset, size)?, start),
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            match data_type {
+                DATA_TYPE_FLOAT64 => bytes_to_f64(read_bytes(buffer, offset, size)?, start),
+                _ => Err(Error::InvalidDataType {
+                    expected: "double",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "double",
+            found: data_type,
+            offset: start,
+        }),
     }
 }
 
-pub(crate) fn read_f64(buffer: &[u8], offset: &mut usize) -> Result<f64, Error> {
+pub fn read_f32(buffer: &[u8], offset: &mut usize) -> Result<f32, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
-        DATA_TYPE_FLOAT64 => Ok(f64::from_bits(
-            bytes_to_usize(read_bytes(buffer, offset, size)?) as u64,
-        )),
+        DATA_TYPE_FLOAT32 => bytes_to_f32(read_bytes(buffer, offset, size)?, start),
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
-                DATA_TYPE_FLOAT64 => Ok(f64::from_bits(bytes_to_usize(read_bytes(
-                    buffer, offset, size,
-                )?) as u64)),
-                _ => Err(Error::InvalidDataType(data_type)),
+                DATA_TYPE_FLOAT32 => bytes_to_f32(read_bytes(buffer, offset, size)?, start),
+                _ => Err(Error::InvalidDataType {
+                    expected: "float",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "float",
+            found: data_type,
+            offset: start,
+        }),
     }
 }
 
+pub fn read_binary<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a [u8], Error> {
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_BYTES => read_bytes(buffer, offset, size),
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            match data_type {
+                DATA_TYPE_BYTES => read_bytes(buffer, offset, size),
+                _ => Err(Error::InvalidDataType {
+                    expected: "bytes",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "bytes",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+#[cfg(feature = "unsafe-str")]
+pub(crate) fn bytes_to_str(buffer: &[u8]) -> Result<&str, Error> {
+    Ok(unsafe { std::str::from_utf8_unchecked(buffer) })
+}
+
+#[cfg(not(feature = "unsafe-str"))]
+pub(crate) fn bytes_to_str(buffer: &[u8]) -> Result<&str, Error> {
+    Ok(std::str::from_utf8(buffer)?)
+}
+
 #[cfg(feature = "unsafe-str")]
-pub(crate) fn read_str<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a str, Error> {
+pub fn read_str<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a str, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_STRING => {
@@ -131,107 +345,552 @@ pub(crate) fn read_str<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a s
         }
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
                 DATA_TYPE_STRING => {
                     Ok(unsafe { std::str::from_utf8_unchecked(read_bytes(buffer, offset, size)?) })
                 }
-                _ => Err(Error::InvalidDataType(data_type)),
+                _ => Err(Error::InvalidDataType {
+                    expected: "string",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "string",
+            found: data_type,
+            offset: start,
+        }),
     }
 }
 
 #[cfg(not(feature = "unsafe-str"))]
-pub(crate) fn read_str<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a str, Error> {
+pub fn read_str<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<&'a str, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_STRING => Ok(std::str::from_utf8(read_bytes(buffer, offset, size)?)?),
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
                 DATA_TYPE_STRING => Ok(std::str::from_utf8(read_bytes(buffer, offset, size)?)?),
-                _ => Err(Error::InvalidDataType(data_type)),
+                _ => Err(Error::InvalidDataType {
+                    expected: "string",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "string",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+/// Decodes a map of the data section into a struct, implemented with
+/// `#[derive(Decoder)]`.
+pub trait Decoder<'a> {
+    /// Decodes the value at `offset` into the field named `key`, returns
+    /// `false` without reading anything if there is no such field.
+    fn decode_field(
+        &mut self,
+        key: &str,
+        buffer: &'a [u8],
+        offset: &mut usize,
+    ) -> Result<bool, Error>;
+
+    /// Whether a key without a field is an error instead of being skipped,
+    /// set with `#[geoip2(deny_unknown_fields)]`.
+    fn deny_unknown_fields(&self) -> bool {
+        false
+    }
+
+    fn decode(&mut self, buffer: &'a [u8], offset: &mut usize) -> Result<(), Error> {
+        let start = *offset;
+        let (data_type, size) = read_control(buffer, offset)?;
+        match data_type {
+            DATA_TYPE_MAP => decode_map(self, buffer, offset, size),
+            DATA_TYPE_POINTER => {
+                let offset = &mut read_pointer(buffer, offset, size)?;
+                let start = *offset;
+                let (data_type, size) = read_control(buffer, offset)?;
+                match data_type {
+                    DATA_TYPE_MAP => decode_map(self, buffer, offset, size),
+                    _ => Err(Error::InvalidDataType {
+                        expected: "map",
+                        found: data_type,
+                        offset: start,
+                    }),
+                }
+            }
+            _ => Err(Error::InvalidDataType {
+                expected: "map",
+                found: data_type,
+                offset: start,
+            }),
+        }
+    }
+}
+
+fn decode_map<'a, D: Decoder<'a> + ?Sized>(
+    decoder: &mut D,
+    buffer: &'a [u8],
+    offset: &mut usize,
+    size: usize,
+) -> Result<(), Error> {
+    for _ in 0..size {
+        let key = read_str(buffer, offset)?;
+        if decoder.decode_field(key, buffer, offset)? {
+            continue;
+        }
+        if decoder.deny_unknown_fields() {
+            return Err(Error::UnknownField(key.into()));
+        }
+        skip_value(buffer, offset)?;
     }
+    Ok(())
 }
 
-#[derive(Default, Debug)]
-pub struct Map<'a>(Vec<(&'a str, &'a str)>);
+/// A map, such as `names`, read straight from the data section when it is
+/// used instead of being decoded into a `Vec` up front.
+#[derive(Default, Clone, Copy)]
+pub struct Map<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+    size: usize,
+}
 
 impl<'a> Map<'a> {
-    pub fn get(&self, key: &'a str) -> Option<&'a str> {
-        for tp in self.0.iter() {
-            if tp.0 == key {
-                return Some(tp.1);
+    /// Returns the string at `key`, only decoding the keys before it.
+    pub fn get(&self, key: &str) -> Result<Option<&'a str>, Error> {
+        let mut offset = self.offset;
+        for _ in 0..self.size {
+            if read_str(self.buffer, &mut offset)? == key {
+                return read_str(self.buffer, &mut offset).map(Some);
             }
+            skip_value(self.buffer, &mut offset)?;
+        }
+        Ok(None)
+    }
+
+    pub fn iter(&self) -> MapIter<'a> {
+        MapIter {
+            buffer: self.buffer,
+            offset: self.offset,
+            remaining: self.size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl std::fmt::Debug for Map<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for entry in self.iter() {
+            match entry {
+                Ok((key, Value::String(value))) => map.entry(&key, &value),
+                Ok((key, value)) => map.entry(&key, &value),
+                Err(_) => return map.finish_non_exhaustive(),
+            };
+        }
+        map.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Map<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.size))?;
+        for entry in self.iter() {
+            let (key, value) = entry.map_err(serde::ser::Error::custom)?;
+            map.serialize_entry(key, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'a> IntoIterator for &Map<'a> {
+    type Item = Result<(&'a str, Value<'a>), Error>;
+    type IntoIter = MapIter<'a>;
+
+    fn into_iter(self) -> MapIter<'a> {
+        self.iter()
+    }
+}
+
+/// Decodes the entries of a `Map` one at a time, stopping after the first
+/// error.
+pub struct MapIter<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+    remaining: usize,
+}
+
+impl<'a> MapIter<'a> {
+    fn read_entry(&mut self) -> Result<(&'a str, Value<'a>), Error> {
+        let key = read_str(self.buffer, &mut self.offset)?;
+        let value = read_value(self.buffer, &mut self.offset)?;
+        Ok((key, value))
+    }
+}
+
+impl<'a> Iterator for MapIter<'a> {
+    type Item = Result<(&'a str, Value<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
-        None
+        let entry = self.read_entry();
+        self.remaining = match entry {
+            Ok(_) => self.remaining - 1,
+            Err(_) => 0,
+        };
+        Some(entry)
     }
 
-    pub fn iter(&'a self) -> std::slice::Iter<'a, (&'a str, &'a str)> {
-        self.0.iter()
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining.min(1), Some(self.remaining))
     }
 }
 
-pub(crate) fn read_map<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<Map<'a>, Error> {
+/// Reads a map without decoding its entries, which `Map` does when they are
+/// used.
+pub fn read_map<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<Map<'a>, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_MAP => {
-            let mut map = Vec::with_capacity(size);
-            for _ in 0..size {
-                map.push((read_str(buffer, offset)?, read_str(buffer, offset)?));
-            }
-            Ok(Map(map))
+            let map = Map {
+                buffer,
+                offset: *offset,
+                size,
+            };
+            *offset = start;
+            skip_value(buffer, offset)?;
+            Ok(map)
         }
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
-                DATA_TYPE_MAP => {
-                    let mut map = Vec::with_capacity(size);
-                    for _ in 0..size {
-                        map.push((read_str(buffer, offset)?, read_str(buffer, offset)?));
-                    }
-                    Ok(Map(map))
-                }
-                _ => Err(Error::InvalidDataType(data_type)),
+                DATA_TYPE_MAP => Ok(Map {
+                    buffer,
+                    offset: *offset,
+                    size,
+                }),
+                _ => Err(Error::InvalidDataType {
+                    expected: "map",
+                    found: data_type,
+                    offset: start,
+                }),
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
+        _ => Err(Error::InvalidDataType {
+            expected: "map",
+            found: data_type,
+            offset: start,
+        }),
     }
 }
 
-pub(crate) fn read_array<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<Vec<&'a str>, Error> {
+/// Reads an array, decoding each of its elements with `read`.
+pub fn read_vec<'a, T>(
+    buffer: &'a [u8],
+    offset: &mut usize,
+    mut read: impl FnMut(&'a [u8], &mut usize) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    let start = *offset;
     let (data_type, size) = read_control(buffer, offset)?;
     match data_type {
         DATA_TYPE_SLICE => {
-            let mut array = Vec::with_capacity(size);
+            let mut array = Vec::with_capacity(capacity(buffer, *offset, size));
             for _ in 0..size {
-                array.push(read_str(buffer, offset)?);
+                array.push(read(buffer, offset)?);
             }
             Ok(array)
         }
         DATA_TYPE_POINTER => {
             let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
             let (data_type, size) = read_control(buffer, offset)?;
             match data_type {
                 DATA_TYPE_SLICE => {
-                    let mut array = Vec::with_capacity(size);
+                    let mut array = Vec::with_capacity(capacity(buffer, *offset, size));
                     for _ in 0..size {
-                        array.push(read_str(buffer, offset)?);
+                        array.push(read(buffer, offset)?);
                     }
                     Ok(array)
                 }
-                _ => Err(Error::InvalidDataType(data_type)),
+                _ => Err(Error::InvalidDataType {
+                    expected: "array",
+                    found: data_type,
+                    offset: start,
+                }),
+            }
+        }
+        _ => Err(Error::InvalidDataType {
+            expected: "array",
+            found: data_type,
+            offset: start,
+        }),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Map(Vec<(&'a str, Value<'a>)>),
+    Array(Vec<Value<'a>>),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    Double(f64),
+    Float(f32),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    Uint128(u128),
+    Int32(i32),
+    Bool(bool),
+}
+
+impl<'a> Value<'a> {
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Map(map) => map.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Map(map) => serializer.collect_map(map.iter().map(|(k, v)| (k, v))),
+            Value::Array(array) => serializer.collect_seq(array),
+            Value::String(value) => serializer.serialize_str(value),
+            Value::Bytes(value) => serializer.serialize_bytes(value),
+            Value::Double(value) => serializer.serialize_f64(*value),
+            Value::Float(value) => serializer.serialize_f32(*value),
+            Value::Uint16(value) => serializer.serialize_u16(*value),
+            Value::Uint32(value) => serializer.serialize_u32(*value),
+            Value::Uint64(value) => serializer.serialize_u64(*value),
+            Value::Uint128(value) => serializer.serialize_u128(*value),
+            Value::Int32(value) => serializer.serialize_i32(*value),
+            Value::Bool(value) => serializer.serialize_bool(*value),
+        }
+    }
+}
+
+/// Maps and arrays nested deeper than this are rejected, as pointers allow a
+/// corrupt database to build cycles.
+pub(crate) const MAX_DEPTH: usize = 512;
+
+pub(crate) fn read_value<'a>(buffer: &'a [u8], offset: &mut usize) -> Result<Value<'a>, Error> {
+    read_nested_value(buffer, offset, 0)
+}
+
+fn read_nested_value<'a>(
+    buffer: &'a [u8],
+    offset: &mut usize,
+    depth: usize,
+) -> Result<Value<'a>, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::MaxDepthExceeded(*offset));
+    }
+    let start = *offset;
+    let (data_type, size) = read_control(buffer, offset)?;
+    match data_type {
+        DATA_TYPE_POINTER => {
+            let offset = &mut read_pointer(buffer, offset, size)?;
+            let start = *offset;
+            let (data_type, size) = read_control(buffer, offset)?;
+            decode_value(buffer, offset, start, data_type, size, depth)
+        }
+        _ => decode_value(buffer, offset, start, data_type, size, depth),
+    }
+}
+
+fn decode_value<'a>(
+    buffer: &'a [u8],
+    offset: &mut usize,
+    start: usize,
+    data_type: u8,
+    size: usize,
+    depth: usize,
+) -> Result<Value<'a>, Error> {
+    Ok(match data_type {
+        DATA_TYPE_MAP => {
+            let mut map = Vec::with_capacity(capacity(buffer, *offset, size));
+            for _ in 0..size {
+                map.push((
+                    read_str(buffer, offset)?,
+                    read_nested_value(buffer, offset, depth + 1)?,
+                ));
+            }
+            Value::Map(map)
+        }
+        DATA_TYPE_SLICE => {
+            let mut array = Vec::with_capacity(capacity(buffer, *offset, size));
+            for _ in 0..size {
+                array.push(read_nested_value(buffer, offset, depth + 1)?);
+            }
+            Value::Array(array)
+        }
+        _ => decode_scalar(buffer, offset, start, data_type, size)?,
+    })
+}
+
+/// Kept out of `decode_value` so the frames of nested maps and arrays stay
+/// small, which matters up to `MAX_DEPTH` in debug builds.
+fn decode_scalar<'a>(
+    buffer: &'a [u8],
+    offset: &mut usize,
+    start: usize,
+    data_type: u8,
+    size: usize,
+) -> Result<Value<'a>, Error> {
+    Ok(match data_type {
+        DATA_TYPE_STRING => Value::String(bytes_to_str(read_bytes(buffer, offset, size)?)?),
+        DATA_TYPE_FLOAT64 => Value::Double(bytes_to_f64(read_bytes(buffer, offset, size)?, start)?),
+        DATA_TYPE_BYTES => Value::Bytes(read_bytes(buffer, offset, size)?),
+        DATA_TYPE_UINT16 => Value::Uint16(bytes_to_u16(read_bytes(buffer, offset, size)?, start)?),
+        DATA_TYPE_UINT32 => Value::Uint32(bytes_to_u32(read_bytes(buffer, offset, size)?, start)?),
+        DATA_TYPE_INT32 => Value::Int32(bytes_to_i32(read_bytes(buffer, offset, size)?, start)?),
+        DATA_TYPE_UINT64 => Value::Uint64(bytes_to_u64(read_bytes(buffer, offset, size)?, start)?),
+        DATA_TYPE_UINT128 => Value::Uint128(bytes_to_checked_u128(
+            read_bytes(buffer, offset, size)?,
+            start,
+        )?),
+        DATA_TYPE_BOOL => Value::Bool(size != 0),
+        DATA_TYPE_FLOAT32 => Value::Float(bytes_to_f32(read_bytes(buffer, offset, size)?, start)?),
+        _ => {
+            return Err(Error::InvalidDataType {
+                expected: "value",
+                found: data_type,
+                offset: start,
+            })
+        }
+    })
+}
+
+/// Follows `path` through nested maps, and arrays by index, skipping every
+/// value off the path, then decodes only the value it leads to.
+pub(crate) fn read_path<'a>(
+    buffer: &'a [u8],
+    mut offset: usize,
+    path: &[&str],
+) -> Result<Option<Value<'a>>, Error> {
+    for key in path {
+        let (mut data_type, mut size) = read_control(buffer, &mut offset)?;
+        if data_type == DATA_TYPE_POINTER {
+            offset = read_pointer(buffer, &mut offset, size)?;
+            (data_type, size) = read_control(buffer, &mut offset)?;
+        }
+        match data_type {
+            DATA_TYPE_MAP => loop {
+                if size == 0 {
+                    return Ok(None);
+                }
+                size -= 1;
+                if read_str(buffer, &mut offset)? == *key {
+                    break;
+                }
+                skip_value(buffer, &mut offset)?;
+            },
+            DATA_TYPE_SLICE => match key.parse::<usize>() {
+                Ok(index) if index < size => {
+                    for _ in 0..index {
+                        skip_value(buffer, &mut offset)?;
+                    }
+                }
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }
+    }
+    read_value(buffer, &mut offset).map(Some)
+}
+
+/// Moves `offset` past the next value without decoding it. Pointers are not
+/// followed, as the value they point to is stored elsewhere.
+pub(crate) fn skip_value(buffer: &[u8], offset: &mut usize) -> Result<(), Error> {
+    // Counting the values left to skip instead of recursing keeps deeply
+    // nested maps and arrays from overflowing the stack.
+    let mut remaining = 1usize;
+    while remaining != 0 {
+        remaining -= 1;
+        let (data_type, size) = read_control(buffer, offset)?;
+        match data_type {
+            DATA_TYPE_POINTER => {
+                read_pointer(buffer, offset, size)?;
+            }
+            DATA_TYPE_MAP => remaining = remaining.saturating_add(size.saturating_mul(2)),
+            DATA_TYPE_SLICE => remaining = remaining.saturating_add(size),
+            DATA_TYPE_BOOL => {}
+            _ => {
+                read_bytes(buffer, offset, size)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every string in the value at `offset`, and in the values its
+/// pointers lead to, is valid UTF-8, even with `unsafe-str`. `checked` holds
+/// the pointer targets already walked.
+pub(crate) fn check_strings(
+    buffer: &[u8],
+    offset: usize,
+    checked: &mut HashSet<usize>,
+) -> Result<(), Error> {
+    let mut pending = vec![offset];
+    while let Some(mut offset) = pending.pop() {
+        let mut remaining = 1usize;
+        while remaining != 0 {
+            remaining -= 1;
+            let (data_type, size) = read_control(buffer, &mut offset)?;
+            match data_type {
+                DATA_TYPE_POINTER => {
+                    let pointer = read_pointer(buffer, &mut offset, size)?;
+                    if checked.insert(pointer) {
+                        pending.push(pointer);
+                    }
+                }
+                DATA_TYPE_MAP => remaining = remaining.saturating_add(size.saturating_mul(2)),
+                DATA_TYPE_SLICE => remaining = remaining.saturating_add(size),
+                DATA_TYPE_BOOL => {}
+                DATA_TYPE_STRING => {
+                    std::str::from_utf8(read_bytes(buffer, &mut offset, size)?)?;
+                }
+                _ => {
+                    read_bytes(buffer, &mut offset, size)?;
+                }
             }
         }
-        _ => Err(Error::InvalidDataType(data_type)),
     }
+    Ok(())
+}
+
+/// Bounds a preallocation by the bytes left in the buffer, as every element
+/// takes at least one byte, so a corrupt size can't trigger a huge allocation.
+pub(crate) fn capacity(buffer: &[u8], offset: usize, size: usize) -> usize {
+    size.min(buffer.len().saturating_sub(offset))
 }
 
 pub(crate) fn bytes_to_usize(buffer: &[u8]) -> usize {
@@ -243,6 +902,83 @@ pub(crate) fn bytes_to_usize(buffer: &[u8]) -> usize {
         .fold(0usize, |acc, &b| (acc << 8) | (b as usize))
 }
 
+pub(crate) fn bytes_to_u128(buffer: &[u8]) -> u128 {
+    if buffer.len() > 16 {
+        return 0;
+    }
+    buffer
+        .iter()
+        .fold(0u128, |acc, &b| (acc << 8) | (b as u128))
+}
+
+fn bytes_to_checked_usize(buffer: &[u8], start: usize) -> Result<usize, Error> {
+    if buffer.len() <= std::mem::size_of::<usize>() {
+        return Ok(bytes_to_usize(buffer));
+    }
+    usize::try_from(bytes_to_checked_u128(buffer, start)?)
+        .map_err(|_| Error::IntegerOverflow(start))
+}
+
+pub(crate) fn bytes_to_checked_u128(buffer: &[u8], start: usize) -> Result<u128, Error> {
+    if buffer.len() > 16 {
+        return Err(Error::IntegerOverflow(start));
+    }
+    Ok(bytes_to_u128(buffer))
+}
+
+/// Doubles are always 8 bytes, unlike integers which may be shortened.
+pub(crate) fn bytes_to_f64(buffer: &[u8], start: usize) -> Result<f64, Error> {
+    match buffer.try_into() {
+        Ok(bytes) => Ok(f64::from_be_bytes(bytes)),
+        Err(_) => Err(Error::InvalidDataSize {
+            data_type: DATA_TYPE_FLOAT64,
+            size: buffer.len(),
+            offset: start,
+        }),
+    }
+}
+
+/// Floats are always 4 bytes.
+pub(crate) fn bytes_to_f32(buffer: &[u8], start: usize) -> Result<f32, Error> {
+    match buffer.try_into() {
+        Ok(bytes) => Ok(f32::from_be_bytes(bytes)),
+        Err(_) => Err(Error::InvalidDataSize {
+            data_type: DATA_TYPE_FLOAT32,
+            size: buffer.len(),
+            offset: start,
+        }),
+    }
+}
+
+pub(crate) fn bytes_to_u16(buffer: &[u8], start: usize) -> Result<u16, Error> {
+    if buffer.len() > 2 {
+        return Err(Error::IntegerOverflow(start));
+    }
+    Ok(bytes_to_usize(buffer) as u16)
+}
+
+pub(crate) fn bytes_to_u32(buffer: &[u8], start: usize) -> Result<u32, Error> {
+    if buffer.len() > 4 {
+        return Err(Error::IntegerOverflow(start));
+    }
+    Ok(bytes_to_usize(buffer) as u32)
+}
+
+pub(crate) fn bytes_to_u64(buffer: &[u8], start: usize) -> Result<u64, Error> {
+    if buffer.len() > 8 {
+        return Err(Error::IntegerOverflow(start));
+    }
+    Ok(buffer.iter().fold(0u64, |acc, &b| (acc << 8) | (b as u64)))
+}
+
+/// Shorter encodings are zero padded, only a full four bytes can be negative.
+pub(crate) fn bytes_to_i32(buffer: &[u8], start: usize) -> Result<i32, Error> {
+    if buffer.len() > 4 {
+        return Err(Error::IntegerOverflow(start));
+    }
+    Ok(bytes_to_usize(buffer) as u32 as i32)
+}
+
 fn bytes_to_usize_with_prefix(prefix: usize, buffer: &[u8]) -> usize {
     (prefix << (buffer.len() * 8)) | bytes_to_usize(buffer)
 }
@@ -0,0 +1,219 @@
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::decoder::{
+    bytes_to_checked_u128, bytes_to_f32, bytes_to_f64, bytes_to_i32, bytes_to_str, bytes_to_u16,
+    bytes_to_u32, bytes_to_u64, read_bytes, read_control, read_pointer, DATA_TYPE_BOOL,
+    DATA_TYPE_BYTES, DATA_TYPE_FLOAT32, DATA_TYPE_FLOAT64, DATA_TYPE_INT32, DATA_TYPE_MAP,
+    DATA_TYPE_POINTER, DATA_TYPE_SLICE, DATA_TYPE_STRING, DATA_TYPE_UINT128, DATA_TYPE_UINT16,
+    DATA_TYPE_UINT32, DATA_TYPE_UINT64, MAX_DEPTH,
+};
+use crate::errors::Error;
+
+struct Control {
+    data_type: u8,
+    size: usize,
+    start: usize,
+    /// The offset to continue from once a pointed to value has been decoded.
+    next: Option<usize>,
+}
+
+pub(crate) struct Deserializer<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Deserializer<'a> {
+    pub(crate) fn new(buffer: &'a [u8], offset: usize) -> Deserializer<'a> {
+        Deserializer {
+            buffer,
+            offset,
+            depth: 0,
+        }
+    }
+
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::MaxDepthExceeded(self.offset));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Reads the next control byte, following a pointer if there is one.
+    fn read_control(&mut self) -> Result<Control, Error> {
+        let start = self.offset;
+        let (data_type, size) = read_control(self.buffer, &mut self.offset)?;
+        if data_type != DATA_TYPE_POINTER {
+            return Ok(Control {
+                data_type,
+                size,
+                start,
+                next: None,
+            });
+        }
+        let pointer = read_pointer(self.buffer, &mut self.offset, size)?;
+        let next = self.offset;
+        self.offset = pointer;
+        let (data_type, size) = read_control(self.buffer, &mut self.offset)?;
+        Ok(Control {
+            data_type,
+            size,
+            start: pointer,
+            next: Some(next),
+        })
+    }
+
+    fn decode<V: Visitor<'a>>(&mut self, control: &Control, visitor: V) -> Result<V::Value, Error> {
+        let size = control.size;
+        let data_type = control.data_type;
+        match data_type {
+            DATA_TYPE_STRING => visitor.visit_borrowed_str(bytes_to_str(read_bytes(
+                self.buffer,
+                &mut self.offset,
+                size,
+            )?)?),
+            DATA_TYPE_FLOAT64 => visitor.visit_f64(bytes_to_f64(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_BYTES => {
+                visitor.visit_borrowed_bytes(read_bytes(self.buffer, &mut self.offset, size)?)
+            }
+            DATA_TYPE_UINT16 => visitor.visit_u16(bytes_to_u16(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_UINT32 => visitor.visit_u32(bytes_to_u32(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_MAP => self.nested(|de| {
+                visitor.visit_map(Access {
+                    de,
+                    remaining: size,
+                })
+            }),
+            DATA_TYPE_INT32 => visitor.visit_i32(bytes_to_i32(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_UINT64 => visitor.visit_u64(bytes_to_u64(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_UINT128 => visitor.visit_u128(bytes_to_checked_u128(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            DATA_TYPE_SLICE => self.nested(|de| {
+                visitor.visit_seq(Access {
+                    de,
+                    remaining: size,
+                })
+            }),
+            DATA_TYPE_BOOL => visitor.visit_bool(size != 0),
+            DATA_TYPE_FLOAT32 => visitor.visit_f32(bytes_to_f32(
+                read_bytes(self.buffer, &mut self.offset, size)?,
+                control.start,
+            )?),
+            _ => Err(Error::InvalidDataType {
+                expected: "value",
+                found: data_type,
+                offset: control.start,
+            }),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let control = self.read_control()?;
+        let value = self.decode(&control, visitor)?;
+        if let Some(next) = control.next {
+            self.offset = next;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let control = self.read_control()?;
+        let value = match control.data_type {
+            DATA_TYPE_STRING => {
+                let value = bytes_to_str(read_bytes(self.buffer, &mut self.offset, control.size)?)?;
+                visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(value))?
+            }
+            _ => self.decode(&control, visitor)?,
+        };
+        if let Some(next) = control.next {
+            self.offset = next;
+        }
+        Ok(value)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct Access<'d, 'a> {
+    de: &'d mut Deserializer<'a>,
+    remaining: usize,
+}
+
+impl<'a> MapAccess<'a> for Access<'_, 'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'a> SeqAccess<'a> for Access<'_, 'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
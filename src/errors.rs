@@ -1,22 +1,235 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::network::Network;
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     InvalidMetadata,
     InvalidRecordSize(u16),
     InvalidDatabaseType(String),
     InvalidSearchTreeSize,
-    InvalidOffset,
-    InvalidDataType(u8),
+    InvalidOffset(usize),
+    InvalidDataType {
+        expected: &'static str,
+        found: u8,
+        offset: usize,
+    },
     InvalidNode,
     UnknownField(String),
     NotFound,
     IPv4Only,
     CorruptSearchTree,
+    InvalidPrefixLength(u8),
+    InvalidNetwork(String),
+    InvalidBuildEpoch(u64),
+    IoError(IoError),
+    DeserializeError(String),
+    MaxDepthExceeded(usize),
+    InvalidMetadataField(&'static str),
+    InvalidDataSectionSeparator,
+    UnreferencedData(usize),
+    IntegerOverflow(usize),
+    InvalidDataSize {
+        data_type: u8,
+        size: usize,
+        offset: usize,
+    },
+    UnknownLanguage(String),
+    ReservedNetwork(Network),
+    DataTooLarge {
+        data_type: u8,
+        size: usize,
+    },
+    RecordSizeTooSmall(u16),
 
     Utf8Error(std::str::Utf8Error),
 }
 
+/// An `std::io::Error`, with the path of the file it happened on if known.
+/// Compared by kind and path, as the error itself can't be.
+#[derive(Debug)]
+pub struct IoError {
+    // Boxed to keep `Error` small, it is returned through deep recursion.
+    path: Option<Box<Path>>,
+    error: std::io::Error,
+}
+
+impl IoError {
+    pub(crate) fn new(path: &Path, error: std::io::Error) -> IoError {
+        IoError {
+            path: Some(path.into()),
+            error,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.error.kind()
+    }
+
+    pub fn error(&self) -> &std::io::Error {
+        &self.error
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &IoError) -> bool {
+        self.path == other.path && self.kind() == other.kind()
+    }
+}
+
+fn data_type_name(data_type: u8) -> &'static str {
+    match data_type {
+        1 => "pointer",
+        2 => "string",
+        3 => "double",
+        4 => "bytes",
+        5 => "uint16",
+        6 => "uint32",
+        7 => "map",
+        8 => "int32",
+        9 => "uint64",
+        10 => "uint128",
+        11 => "array",
+        12 => "data cache container",
+        13 => "end marker",
+        14 => "boolean",
+        15 => "float",
+        _ => "unknown data type",
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidMetadata => write!(f, "metadata section not found or invalid"),
+            Error::InvalidRecordSize(size) => write!(f, "invalid record size {}", size),
+            Error::InvalidDatabaseType(database_type) => {
+                write!(f, "unexpected database type {:?}", database_type)
+            }
+            Error::InvalidSearchTreeSize => write!(f, "search tree is larger than the database"),
+            Error::InvalidOffset(offset) => {
+                write!(
+                    f,
+                    "read past the end of the data section at offset {}",
+                    offset
+                )
+            }
+            Error::InvalidDataType {
+                expected,
+                found,
+                offset,
+            } => write!(
+                f,
+                "invalid data type at offset {}: expected {}, found {} ({})",
+                offset,
+                expected,
+                data_type_name(*found),
+                found
+            ),
+            Error::InvalidNode => write!(f, "invalid search tree node"),
+            Error::UnknownField(field) => write!(f, "unknown field {:?}", field),
+            Error::NotFound => write!(f, "address not found"),
+            Error::IPv4Only => write!(f, "IPv6 lookup in an IPv4-only database"),
+            Error::CorruptSearchTree => write!(f, "search tree points outside the data section"),
+            Error::InvalidPrefixLength(prefix_len) => {
+                write!(f, "invalid prefix length {}", prefix_len)
+            }
+            Error::InvalidNetwork(network) => write!(f, "invalid network {:?}", network),
+            Error::InvalidBuildEpoch(build_epoch) => {
+                write!(
+                    f,
+                    "database build {} is older than the current one",
+                    build_epoch
+                )
+            }
+            Error::IoError(err) => match err.path() {
+                Some(path) => write!(f, "{}: {}", path.display(), err.error),
+                None => write!(f, "i/o error: {}", err.error),
+            },
+            Error::DeserializeError(msg) => write!(f, "{}", msg),
+            Error::MaxDepthExceeded(offset) => {
+                write!(f, "data nested too deeply at offset {}", offset)
+            }
+            Error::InvalidMetadataField(field) => write!(f, "invalid metadata field {}", field),
+            Error::InvalidDataSectionSeparator => {
+                write!(f, "data section separator is not zeroed")
+            }
+            Error::UnreferencedData(offset) => {
+                write!(
+                    f,
+                    "data at offset {} is not referenced by the search tree",
+                    offset
+                )
+            }
+            Error::IntegerOverflow(offset) => {
+                write!(f, "integer at offset {} is too large for its type", offset)
+            }
+            Error::InvalidDataSize {
+                data_type,
+                size,
+                offset,
+            } => write!(
+                f,
+                "invalid size {} for a {} at offset {}",
+                size,
+                data_type_name(*data_type),
+                offset
+            ),
+            Error::UnknownLanguage(language) => {
+                write!(f, "language {:?} is not in the database", language)
+            }
+            Error::ReservedNetwork(network) => {
+                write!(f, "network {} is reserved or aliased", network)
+            }
+            Error::DataTooLarge { data_type, size } => write!(
+                f,
+                "{} of size {} is too large to write",
+                data_type_name(*data_type),
+                size
+            ),
+            Error::RecordSizeTooSmall(size) => write!(
+                f,
+                "search tree and data section don't fit in {} bit records",
+                size
+            ),
+            Error::Utf8Error(err) => write!(f, "invalid string: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Utf8Error(err) => Some(err),
+            Error::IoError(err) => Some(&err.error),
+            _ => None,
+        }
+    }
+}
+
 impl From<std::str::Utf8Error> for Error {
     fn from(err: std::str::Utf8Error) -> Error {
         Error::Utf8Error(err)
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        Error::IoError(IoError {
+            path: None,
+            error: err,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::DeserializeError(msg.to_string())
+    }
+}
@@ -1,10 +1,52 @@
+// Lets generated code refer to `::geoip2` from inside this crate too.
+extern crate self as geoip2;
+
 mod decoder;
 mod errors;
+mod locales;
 mod metadata;
 pub mod models;
+mod network;
+mod owned;
 mod reader;
+mod reload;
+#[cfg(feature = "watch")]
+mod watch;
+mod writer;
 
-pub use errors::Error;
+pub use decoder::{Decoder, Map, MapIter, Value};
+pub use errors::{Error, IoError};
+pub use geoip2_codegen::Decoder;
+pub use locales::{Locales, Names};
+pub use network::Network;
+pub use owned::OwnedReader;
 pub use reader::{
-    AnonymousIP, City, ConnectionType, Country, Domain, Enterprise, Reader, ASN, ISP,
+    AnonymousIP, Any, City, ConnectionType, Country, Domain, Enterprise, Networks, NetworksOnly,
+    Reader, Record, ASN, ISP,
 };
+pub use reload::ReloadableReader;
+#[cfg(feature = "watch")]
+pub use watch::Watcher;
+pub use writer::{InsertStrategy, Writer};
+
+/// Used by the code `#[derive(Decoder)]` generates, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::decoder::{
+        read_binary, read_bool, read_f32, read_f64, read_i32, read_map, read_str, read_u128,
+        read_u16, read_u32, read_u64, read_usize, read_vec,
+    };
+    pub use crate::errors::Error;
+    #[cfg(feature = "serde")]
+    pub use serde;
+
+    /// Writes the entries of a struct into a map, so flattened fields can
+    /// share the map of their parent.
+    #[cfg(feature = "serde")]
+    pub trait SerializeFields {
+        fn serialize_fields<M: serde::ser::SerializeMap>(
+            &self,
+            map: &mut M,
+        ) -> Result<(), M::Error>;
+    }
+}
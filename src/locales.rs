@@ -0,0 +1,102 @@
+use crate::decoder::Map;
+use crate::errors::Error;
+use crate::metadata::Metadata;
+use crate::reader::{City, Country, Enterprise, Reader};
+
+/// Preferred languages in order, each one checked to be in the database,
+/// created with `Reader::locales`.
+#[derive(Clone, Copy, Debug)]
+pub struct Locales<'l>(&'l [&'l str]);
+
+impl<'l> Locales<'l> {
+    pub(crate) fn new(languages: &'l [&'l str], metadata: &Metadata) -> Result<Locales<'l>, Error> {
+        for language in languages {
+            if !metadata.languages.contains(language) {
+                return Err(Error::UnknownLanguage(language.to_string()));
+            }
+        }
+        Ok(Locales(languages))
+    }
+
+    /// Returns the name in the first preferred language found in `names`.
+    pub fn name<'a>(&self, names: Option<Map<'a>>) -> Result<Option<&'a str>, Error> {
+        let names = match names {
+            Some(names) => names,
+            None => return Ok(None),
+        };
+        for language in self.0 {
+            if let Some(name) = names.get(language)? {
+                return Ok(Some(name));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a, T> Reader<'a, T> {
+    /// Checks `languages` against the languages of the database, e.g.
+    /// `&["pt-BR", "es", "en"]`, to resolve names with a fallback.
+    pub fn locales<'l>(&self, languages: &'l [&'l str]) -> Result<Locales<'l>, Error> {
+        Locales::new(languages, self.get_metadata())
+    }
+}
+
+/// The names of a record in the first preferred language each one has.
+#[derive(Default, Debug, PartialEq)]
+pub struct Names<'a> {
+    pub continent: Option<&'a str>,
+    pub country: Option<&'a str>,
+    /// In the order of the record's subdivisions, `None` for those without a
+    /// name in any preferred language.
+    pub subdivisions: Vec<Option<&'a str>>,
+    pub city: Option<&'a str>,
+    pub represented_country: Option<&'a str>,
+}
+
+impl<'a> Country<'a> {
+    pub fn names(&self, locales: &Locales) -> Result<Names<'a>, Error> {
+        Ok(Names {
+            continent: locales.name(self.continent.as_ref().and_then(|v| v.names))?,
+            country: locales.name(self.country.as_ref().and_then(|v| v.names))?,
+            represented_country: locales
+                .name(self.represented_country.as_ref().and_then(|v| v.names))?,
+            ..Default::default()
+        })
+    }
+}
+
+impl<'a> City<'a> {
+    pub fn names(&self, locales: &Locales) -> Result<Names<'a>, Error> {
+        Ok(Names {
+            continent: locales.name(self.continent.as_ref().and_then(|v| v.names))?,
+            country: locales.name(self.country.as_ref().and_then(|v| v.names))?,
+            subdivisions: self
+                .subdivisions
+                .iter()
+                .flatten()
+                .map(|v| locales.name(v.names))
+                .collect::<Result<_, _>>()?,
+            city: locales.name(self.city.as_ref().and_then(|v| v.names))?,
+            represented_country: locales
+                .name(self.represented_country.as_ref().and_then(|v| v.names))?,
+        })
+    }
+}
+
+impl<'a> Enterprise<'a> {
+    pub fn names(&self, locales: &Locales) -> Result<Names<'a>, Error> {
+        Ok(Names {
+            continent: locales.name(self.continent.as_ref().and_then(|v| v.names))?,
+            country: locales.name(self.country.as_ref().and_then(|v| v.names))?,
+            subdivisions: self
+                .subdivisions
+                .iter()
+                .flatten()
+                .map(|v| locales.name(v.names))
+                .collect::<Result<_, _>>()?,
+            city: locales.name(self.city.as_ref().and_then(|v| v.names))?,
+            represented_country: locales
+                .name(self.represented_country.as_ref().and_then(|v| v.names))?,
+        })
+    }
+}
@@ -1,15 +1,12 @@
-use crate::decoder::{
-    read_array, read_control, read_map, read_pointer, read_str, read_usize, Map, DATA_TYPE_MAP,
-    DATA_TYPE_POINTER,
-};
-use crate::errors::Error;
+use crate::decoder::Map;
 use geoip2_codegen::Decoder;
 
-const METADATA_START_MARKER: [u8; 14] = [
+pub(crate) const METADATA_START_MARKER: [u8; 14] = [
     0xAB, 0xCD, 0xEF, 0x4d, 0x61, 0x78, 0x4d, 0x69, 0x6e, 0x64, 0x2e, 0x63, 0x6f, 0x6d,
 ];
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Metadata<'a> {
     pub binary_format_major_version: u16,
     pub binary_format_minor_version: u16,
@@ -1,11 +1,8 @@
-use crate::decoder::{
-    read_bool, read_control, read_f64, read_map, read_pointer, read_str, read_usize, Map,
-    DATA_TYPE_MAP, DATA_TYPE_POINTER,
-};
-use crate::errors::Error;
+use crate::decoder::Map;
 use geoip2_codegen::Decoder;
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Continent<'a> {
     pub geoname_id: Option<u32>,
     pub code: Option<&'a str>,
@@ -13,6 +10,7 @@ pub struct Continent<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Country<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
@@ -21,6 +19,7 @@ pub struct Country<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterpriseCountry<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
@@ -30,25 +29,30 @@ pub struct EnterpriseCountry<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct RepresentedCountry<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
     pub names: Option<Map<'a>>,
     pub is_in_european_union: Option<bool>,
+    #[geoip2(rename = "type")]
     pub country_type: Option<&'a str>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterpriseRepresentedCountry<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
     pub names: Option<Map<'a>>,
     pub is_in_european_union: Option<bool>,
+    #[geoip2(rename = "type")]
     pub country_type: Option<&'a str>,
     pub confidence: Option<u16>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Subdivision<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
@@ -56,6 +60,7 @@ pub struct Subdivision<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterpriseSubdivision<'a> {
     pub geoname_id: Option<u32>,
     pub iso_code: Option<&'a str>,
@@ -64,12 +69,14 @@ pub struct EnterpriseSubdivision<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct City<'a> {
     pub geoname_id: Option<u32>,
     pub names: Option<Map<'a>>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterpriseCity<'a> {
     pub geoname_id: Option<u32>,
     pub names: Option<Map<'a>>,
@@ -77,6 +84,7 @@ pub struct EnterpriseCity<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Location<'a> {
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
@@ -86,23 +94,27 @@ pub struct Location<'a> {
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Postal<'a> {
     pub code: Option<&'a str>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterprisePostal<'a> {
     pub code: Option<&'a str>,
     pub confidence: Option<u16>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Traits {
     pub is_anonymous_proxy: Option<bool>,
     pub is_satellite_provider: Option<bool>,
 }
 
 #[derive(Default, Debug, Decoder)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct EnterpriseTraits<'a> {
     pub is_anonymous_proxy: Option<bool>,
     pub is_satellite_provider: Option<bool>,
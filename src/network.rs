@@ -0,0 +1,72 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use crate::errors::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Network {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl Network {
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<Network, Error> {
+        let address = match address {
+            IpAddr::V4(ip) => {
+                if prefix_len > 32 {
+                    return Err(Error::InvalidPrefixLength(prefix_len));
+                }
+                let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) => {
+                if prefix_len > 128 {
+                    return Err(Error::InvalidPrefixLength(prefix_len));
+                }
+                let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+        };
+        Ok(Network {
+            address,
+            prefix_len,
+        })
+    }
+
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match Network::new(address, self.prefix_len) {
+            Ok(network) => network.address == self.address,
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_len)
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Network, Error> {
+        let (address, prefix_len) = match s.split_once('/') {
+            Some(parts) => parts,
+            None => return Err(Error::InvalidNetwork(s.into())),
+        };
+        match (address.parse(), prefix_len.parse()) {
+            (Ok(address), Ok(prefix_len)) => Network::new(address, prefix_len),
+            _ => Err(Error::InvalidNetwork(s.into())),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(feature = "mmap")]
+use std::fs::File;
+use std::path::Path;
+
+use crate::errors::{Error, IoError};
+use crate::reader::{Reader, Record};
+
+/// A `Reader` that owns its database buffer.
+///
+/// The record type is named with a `'static` lifetime, e.g.
+/// `OwnedReader<City<'static>>`, while results returned through
+/// [`OwnedReader::reader`] borrow from the `OwnedReader` itself.
+pub struct OwnedReader<T: 'static> {
+    // Declared before `buffer` so it is dropped first.
+    reader: Reader<'static, T>,
+    buffer: Box<dyn AsRef<[u8]> + Send + Sync>,
+}
+
+impl<T: Record + 'static> OwnedReader<T> {
+    pub fn from_source<S>(source: S) -> Result<OwnedReader<T>, Error>
+    where
+        S: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        let buffer: Box<dyn AsRef<[u8]> + Send + Sync> = Box::new(source);
+        // SAFETY: the bytes are owned by `buffer`, which is boxed, never
+        // mutated and outlives `reader`.
+        let bytes = unsafe { &*((*buffer).as_ref() as *const [u8]) };
+        Ok(OwnedReader {
+            reader: Reader::from_bytes_checked(bytes)?,
+            buffer,
+        })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OwnedReader<T>, Error> {
+        let path = path.as_ref();
+        let buffer = std::fs::read(path).map_err(|err| Error::IoError(IoError::new(path, err)))?;
+        OwnedReader::from_source(buffer)
+    }
+
+    /// Maps the database file read-only instead of copying it onto the heap.
+    ///
+    /// The file must not be modified in place while it is mapped; replacing it
+    /// with a rename, as `geoipupdate` does, is fine.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<OwnedReader<T>, Error> {
+        let path = path.as_ref();
+        let io_error = |err| Error::IoError(IoError::new(path, err));
+        let file = File::open(path).map_err(io_error)?;
+        // SAFETY: the mapping is read-only, see the note on in-place modification above.
+        let mmap = unsafe { memmap2::Mmap::map(&file).map_err(io_error)? };
+        OwnedReader::from_source(mmap)
+    }
+
+    pub fn reader(&self) -> &Reader<'_, T::Of<'_>> {
+        // SAFETY: `T::Of<'_>` is `T` with its lifetime shortened to the
+        // borrow of `self`, so both readers have the same layout.
+        unsafe { &*(&self.reader as *const Reader<'static, T>).cast::<Reader<'_, T::Of<'_>>>() }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        (*self.buffer).as_ref()
+    }
+}
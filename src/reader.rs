@@ -1,22 +1,36 @@
+use std::collections::HashSet;
 use std::marker::PhantomData;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-use crate::decoder::{
-    read_bool, read_control, read_pointer, read_str, read_usize, DATA_TYPE_MAP, DATA_TYPE_POINTER,
-    DATA_TYPE_SLICE,
-};
+#[cfg(feature = "serde")]
+use crate::decoder::de::Deserializer;
+use crate::decoder::{check_strings, read_path, read_value, Decoder, Value};
 use crate::errors::Error;
-use crate::metadata::Metadata;
+use crate::metadata::{Metadata, METADATA_START_MARKER};
 use crate::models;
+use crate::network::Network;
 use geoip2_codegen::reader;
 
 const DATA_SECTION_SEPARATOR_SIZE: usize = 16;
 
+mod private {
+    pub trait Sealed {}
+}
+
+/// A record type a `Reader` can be opened for, implemented by the `reader` macro.
+pub trait Record: private::Sealed {
+    /// The same record borrowing its strings for `'r`.
+    type Of<'r>;
+
+    fn is_valid_database_type(database_type: &str) -> bool;
+}
+
 pub struct Reader<'a, T> {
     t: PhantomData<&'a T>,
     pub(crate) metadata: Metadata<'a>,
     pub(crate) decoder_buffer: &'a [u8],
     node_buffer: &'a [u8],
+    separator_buffer: &'a [u8],
     node_offset_mult: usize,
     ip_v4_start: usize,
     ip_v4_start_bit_depth: usize,
@@ -28,22 +42,24 @@ impl<'a, T> Reader<'a, T> {
             Some(index) => index,
             None => return Err(Error::InvalidMetadata),
         };
+        let data_section_end = metadata_start - METADATA_START_MARKER.len();
         let mut metadata = Metadata::default();
-        metadata.from_bytes(buffer, &mut metadata_start)?;
+        metadata.decode(buffer, &mut metadata_start)?;
         if metadata.record_size != 24 && metadata.record_size != 28 && metadata.record_size != 32 {
             return Err(Error::InvalidRecordSize(metadata.record_size));
         }
         let node_offset_mult = (metadata.record_size as usize) / 4;
-        let search_tree_size = (metadata.node_count as usize) * node_offset_mult;
+        let search_tree_size = match (metadata.node_count as usize).checked_mul(node_offset_mult) {
+            Some(size) if size + DATA_SECTION_SEPARATOR_SIZE <= data_section_end => size,
+            _ => return Err(Error::InvalidSearchTreeSize),
+        };
         let data_section_start = search_tree_size + DATA_SECTION_SEPARATOR_SIZE;
-        if data_section_start > metadata_start {
-            return Err(Error::InvalidSearchTreeSize);
-        }
         let mut reader = Reader {
             t: PhantomData,
             metadata,
-            decoder_buffer: &buffer[data_section_start..metadata_start],
+            decoder_buffer: &buffer[data_section_start..data_section_end],
             node_buffer: &buffer[..search_tree_size],
+            separator_buffer: &buffer[search_tree_size..data_section_start],
             node_offset_mult,
             ip_v4_start: 0,
             ip_v4_start_bit_depth: 0,
@@ -61,27 +77,39 @@ impl<'a, T> Reader<'a, T> {
         Ok(reader)
     }
 
+    pub(crate) fn from_bytes_checked(buffer: &'a [u8]) -> Result<Reader<'a, T>, Error>
+    where
+        T: Record,
+    {
+        let reader = Reader::from_bytes_raw(buffer)?;
+        if !T::is_valid_database_type(reader.metadata.database_type) {
+            return Err(Error::InvalidDatabaseType(
+                reader.metadata.database_type.into(),
+            ));
+        }
+        Ok(reader)
+    }
+
     fn find_address_in_tree<const N: usize>(
         &self,
         ip: &[u8; N],
         mut node: usize,
-    ) -> Result<usize, Error> {
+    ) -> Result<(usize, usize), Error> {
         let node_count = self.metadata.node_count as usize;
-        for i in 0..(N * 8) {
-            if node >= node_count {
-                break;
-            }
-            let bit = 1 & (ip[i >> 3] >> (7 - (i % 8)));
+        let mut prefix_len = 0usize;
+        while prefix_len < N * 8 && node < node_count {
+            let bit = 1 & (ip[prefix_len >> 3] >> (7 - (prefix_len % 8)));
             let offset = node * self.node_offset_mult;
             node = if bit == 0 {
                 self.read_left(offset)
             } else {
                 self.read_right(offset)
-            }
+            };
+            prefix_len += 1;
         }
         match node_count {
-            n if n == node => Ok(0),
-            n if node > n => Ok(node),
+            n if n == node => Ok((0, prefix_len)),
+            n if node > n => Ok((node, prefix_len)),
             _ => Err(Error::InvalidNode),
         }
     }
@@ -99,13 +127,13 @@ impl<'a, T> Reader<'a, T> {
                     | ((self.node_buffer[node_number + 1] as usize) << 8)
                     | (self.node_buffer[node_number + 2] as usize)
             }
-            32 => {
+            // 32, the record size is validated in `from_bytes_raw`.
+            _ => {
                 ((self.node_buffer[node_number] as usize) << 24)
                     | ((self.node_buffer[node_number + 1] as usize) << 16)
                     | ((self.node_buffer[node_number + 2] as usize) << 8)
                     | (self.node_buffer[node_number + 3] as usize)
             }
-            _ => panic!(),
         }
     }
 
@@ -122,18 +150,18 @@ impl<'a, T> Reader<'a, T> {
                     | ((self.node_buffer[node_number + 4] as usize) << 8)
                     | (self.node_buffer[node_number + 5] as usize)
             }
-            32 => {
+            // 32, the record size is validated in `from_bytes_raw`.
+            _ => {
                 ((self.node_buffer[node_number + 4] as usize) << 24)
                     | ((self.node_buffer[node_number + 5] as usize) << 16)
                     | ((self.node_buffer[node_number + 6] as usize) << 8)
                     | (self.node_buffer[node_number + 7] as usize)
             }
-            _ => panic!(),
         }
     }
 
-    fn lookup_pointer(&self, address: IpAddr) -> Result<usize, Error> {
-        let pointer = match address {
+    fn lookup_pointer(&self, address: IpAddr) -> Result<(usize, usize), Error> {
+        let (pointer, prefix_len) = match address {
             IpAddr::V4(ip) => {
                 let octets = ip.octets();
                 self.find_address_in_tree(&octets, self.ip_v4_start)?
@@ -149,21 +177,333 @@ impl<'a, T> Reader<'a, T> {
         if pointer == 0 {
             return Err(Error::NotFound);
         }
-        Ok(pointer)
+        Ok((pointer, prefix_len))
     }
 
     fn get_offset(&self, address: IpAddr) -> Result<usize, Error> {
-        let pointer = self.lookup_pointer(address)?;
-        let offset = pointer - self.metadata.node_count as usize - DATA_SECTION_SEPARATOR_SIZE;
-        if offset >= self.decoder_buffer.len() {
-            return Err(Error::CorruptSearchTree);
+        Ok(self.get_offset_with_prefix(address)?.0)
+    }
+
+    fn get_offset_with_prefix(&self, address: IpAddr) -> Result<(usize, usize), Error> {
+        let (pointer, prefix_len) = self.lookup_pointer(address)?;
+        Ok((self.resolve_pointer(pointer)?, prefix_len))
+    }
+
+    fn resolve_pointer(&self, pointer: usize) -> Result<usize, Error> {
+        match pointer.checked_sub(self.metadata.node_count as usize + DATA_SECTION_SEPARATOR_SIZE) {
+            Some(offset) if offset < self.decoder_buffer.len() => Ok(offset),
+            _ => Err(Error::CorruptSearchTree),
         }
-        Ok(offset)
+    }
+
+    fn get_network(&self, address: IpAddr, prefix_len: usize) -> Result<Network, Error> {
+        // The IPv4 subtree of an IPv6 database may start at a leaf above ::/96,
+        // in which case every IPv4 address shares the network of that leaf.
+        if address.is_ipv4() && self.metadata.ip_version == 6 && self.ip_v4_start_bit_depth != 96 {
+            return Network::new(
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                self.ip_v4_start_bit_depth as u8,
+            );
+        }
+        Network::new(address, prefix_len as u8)
+    }
+
+    pub fn lookup_value(&self, address: IpAddr) -> Result<Value<'a>, Error> {
+        read_value(self.decoder_buffer, &mut self.get_offset(address)?)
+    }
+
+    /// Looks up `address` decoding only the value at `path`, e.g.
+    /// `&["country", "iso_code"]` or `&["subdivisions", "0", "iso_code"]`.
+    /// Returns `None` if the record has no such value.
+    pub fn lookup_path(&self, address: IpAddr, path: &[&str]) -> Result<Option<Value<'a>>, Error> {
+        read_path(self.decoder_buffer, self.get_offset(address)?, path)
+    }
+
+    /// Looks up `address` decoding its record into any `#[derive(Decoder)]` type.
+    pub fn lookup_decoded<D: Decoder<'a> + Default>(&self, address: IpAddr) -> Result<D, Error> {
+        let mut result = D::default();
+        result.decode(self.decoder_buffer, &mut self.get_offset(address)?)?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn lookup_as<D: serde::Deserialize<'a>>(&self, address: IpAddr) -> Result<D, Error> {
+        let mut deserializer = Deserializer::new(self.decoder_buffer, self.get_offset(address)?);
+        D::deserialize(&mut deserializer)
     }
 
     pub fn get_metadata(&self) -> &Metadata<'a> {
         &self.metadata
     }
+
+    /// Checks the whole database, like `mmdbverify`: the metadata, every node of
+    /// the search tree, the data section separator and every record in the
+    /// data section. Strings are checked for valid UTF-8 even with `unsafe-str`.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.verify_metadata()?;
+        let offsets = self.verify_search_tree()?;
+        if self.separator_buffer.iter().any(|&byte| byte != 0) {
+            return Err(Error::InvalidDataSectionSeparator);
+        }
+        self.verify_data_section(offsets)
+    }
+
+    fn verify_metadata(&self) -> Result<(), Error> {
+        let metadata = &self.metadata;
+        if metadata.binary_format_major_version != 2 {
+            return Err(Error::InvalidMetadataField("binary_format_major_version"));
+        }
+        if metadata.binary_format_minor_version != 0 {
+            return Err(Error::InvalidMetadataField("binary_format_minor_version"));
+        }
+        if metadata.ip_version != 4 && metadata.ip_version != 6 {
+            return Err(Error::InvalidMetadataField("ip_version"));
+        }
+        if metadata.node_count == 0 {
+            return Err(Error::InvalidMetadataField("node_count"));
+        }
+        if metadata.database_type.is_empty() {
+            return Err(Error::InvalidMetadataField("database_type"));
+        }
+        if metadata.description.is_empty() {
+            return Err(Error::InvalidMetadataField("description"));
+        }
+        Ok(())
+    }
+
+    fn verify_search_tree(&self) -> Result<HashSet<usize>, Error> {
+        let node_count = self.metadata.node_count as usize;
+        // Every node but the root must have exactly one parent, except for the
+        // IPv4 subtree which its aliases lead to as well. Shared nodes or cycles
+        // would make walking the tree exponential or endless.
+        let mut visited = Visited::new(node_count);
+        let mut offsets = HashSet::new();
+        for node in 0..node_count {
+            let offset = node * self.node_offset_mult;
+            for record in [self.read_left(offset), self.read_right(offset)] {
+                if record > node_count {
+                    offsets.insert(self.resolve_pointer(record)?);
+                } else if record < node_count
+                    && (record == 0 || !visited.insert(record))
+                    && !self.is_ipv4_alias(record)
+                {
+                    return Err(Error::InvalidNode);
+                }
+            }
+        }
+        Ok(offsets)
+    }
+
+    /// Whether `node` is the IPv4 subtree of an IPv6 database, reachable
+    /// through aliases such as ::ffff:0:0/96 besides ::/96.
+    fn is_ipv4_alias(&self, node: usize) -> bool {
+        node == self.ip_v4_start
+            && self.ip_v4_start_bit_depth == 96
+            && self.ip_v4_start < self.metadata.node_count as usize
+    }
+
+    fn verify_data_section(&self, mut offsets: HashSet<usize>) -> Result<(), Error> {
+        let mut checked = HashSet::new();
+        let mut offset = 0;
+        while offset < self.decoder_buffer.len() {
+            if !offsets.remove(&offset) {
+                return Err(Error::UnreferencedData(offset));
+            }
+            // Before decoding, as `unsafe-str` doesn't check strings.
+            check_strings(self.decoder_buffer, offset, &mut checked)?;
+            read_value(self.decoder_buffer, &mut offset)?;
+        }
+        match offsets.into_iter().min() {
+            Some(offset) => Err(Error::InvalidOffset(offset)),
+            None => Ok(()),
+        }
+    }
+
+    fn bit_count(&self) -> usize {
+        if self.metadata.ip_version == 4 {
+            32
+        } else {
+            128
+        }
+    }
+
+    pub fn networks(&self) -> Networks<'_, 'a, T> {
+        Networks {
+            reader: self,
+            bit_count: self.bit_count(),
+            stack: vec![(0, 0, 0)],
+            visited: Visited::new(self.metadata.node_count as usize),
+        }
+    }
+
+    pub fn within(&self, network: Network) -> Result<Networks<'_, 'a, T>, Error> {
+        let prefix_len = network.prefix_len() as usize;
+        let (ip, prefix_len) = match network.address() {
+            IpAddr::V4(ip) if self.metadata.ip_version == 4 => (u32::from(ip) as u128, prefix_len),
+            IpAddr::V4(ip) => (u32::from(ip) as u128, prefix_len + 96),
+            IpAddr::V6(_) if self.metadata.ip_version == 4 => return Err(Error::IPv4Only),
+            IpAddr::V6(ip) => (u128::from(ip), prefix_len),
+        };
+        let bit_count = self.bit_count();
+        let node_count = self.metadata.node_count as usize;
+        let mut node = 0usize;
+        let mut depth = 0usize;
+        while depth < prefix_len && node < node_count {
+            let bit = 1 & (ip >> (bit_count - depth - 1));
+            let offset = node * self.node_offset_mult;
+            node = if bit == 0 {
+                self.read_left(offset)
+            } else {
+                self.read_right(offset)
+            };
+            depth += 1;
+        }
+        // A record found above the requested prefix covers the whole network,
+        // so it is yielded with its own, larger, network.
+        let ip = match depth {
+            0 => 0,
+            _ => ip >> (bit_count - depth) << (bit_count - depth),
+        };
+        Ok(Networks {
+            reader: self,
+            bit_count,
+            stack: vec![(node, ip, depth)],
+            visited: Visited::new(node_count),
+        })
+    }
+}
+
+pub struct Networks<'r, 'a, T> {
+    reader: &'r Reader<'a, T>,
+    bit_count: usize,
+    stack: Vec<(usize, u128, usize)>,
+    visited: Visited,
+}
+
+/// A bitset of the nodes already walked.
+struct Visited(Vec<u64>);
+
+impl Visited {
+    fn new(node_count: usize) -> Visited {
+        Visited(vec![0; node_count.div_ceil(64)])
+    }
+
+    /// Marks `node` as visited, returning `false` if it already was.
+    fn insert(&mut self, node: usize) -> bool {
+        let (word, bit) = (&mut self.0[node / 64], 1 << (node % 64));
+        let inserted = *word & bit == 0;
+        *word |= bit;
+        inserted
+    }
+}
+
+impl<'r, 'a, T> Networks<'r, 'a, T> {
+    /// Walks the same networks without decoding their records, which is
+    /// faster when only the networks are needed.
+    pub fn without_records(self) -> NetworksOnly<'r, 'a, T> {
+        NetworksOnly(self)
+    }
+
+    fn next_offset(&mut self) -> Option<Result<(Network, usize), Error>> {
+        let reader = self.reader;
+        let node_count = reader.metadata.node_count as usize;
+        while let Some((node, ip, prefix_len)) = self.stack.pop() {
+            if node > node_count {
+                return Some(
+                    reader
+                        .resolve_pointer(node)
+                        .and_then(|offset| Ok((self.get_network(ip, prefix_len)?, offset))),
+                );
+            }
+            if node == node_count {
+                continue;
+            }
+            if prefix_len >= self.bit_count || !self.visited.insert(node) {
+                return Some(Err(Error::InvalidNode));
+            }
+            let offset = node * reader.node_offset_mult;
+            let right_ip = ip | (1 << (self.bit_count - prefix_len - 1));
+            for (child, ip) in [
+                (reader.read_right(offset), right_ip),
+                (reader.read_left(offset), ip),
+            ] {
+                // The IPv4 subtree is also reachable through aliases such as
+                // ::ffff:0:0/96, only walk it once under ::/96.
+                if reader.is_ipv4_alias(child) && (prefix_len + 1 != 96 || ip != 0) {
+                    continue;
+                }
+                self.stack.push((child, ip, prefix_len + 1));
+            }
+        }
+        None
+    }
+
+    fn get_network(&self, ip: u128, prefix_len: usize) -> Result<Network, Error> {
+        let (address, prefix_len) = if self.bit_count == 32 {
+            (IpAddr::V4(Ipv4Addr::from(ip as u32)), prefix_len)
+        } else if prefix_len >= 96 && ip >> 32 == 0 {
+            (IpAddr::V4(Ipv4Addr::from(ip as u32)), prefix_len - 96)
+        } else {
+            (IpAddr::V6(Ipv6Addr::from(ip)), prefix_len)
+        };
+        Network::new(address, prefix_len as u8)
+    }
+}
+
+/// The networks of a database, from [`Networks::without_records`].
+pub struct NetworksOnly<'r, 'a, T>(Networks<'r, 'a, T>);
+
+impl<T> Iterator for NetworksOnly<'_, '_, T> {
+    type Item = Result<Network, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next_offset()?.map(|(network, _)| network))
+    }
+}
+
+/// A record type accepting any database, decoded as a [`Value`].
+#[derive(Default, Debug)]
+pub struct Any;
+
+impl private::Sealed for Any {}
+
+impl Record for Any {
+    type Of<'r> = Any;
+
+    fn is_valid_database_type(_database_type: &str) -> bool {
+        true
+    }
+}
+
+impl<'a> Reader<'a, Any> {
+    pub fn from_bytes(buffer: &[u8]) -> Result<Reader<'_, Any>, Error> {
+        Reader::from_bytes_checked(buffer)
+    }
+
+    pub fn lookup(&self, address: IpAddr) -> Result<Value<'a>, Error> {
+        self.lookup_value(address)
+    }
+
+    pub fn lookup_network(&self, address: IpAddr) -> Result<(Value<'a>, Network), Error> {
+        let (mut offset, prefix_len) = self.get_offset_with_prefix(address)?;
+        Ok((
+            read_value(self.decoder_buffer, &mut offset)?,
+            self.get_network(address, prefix_len)?,
+        ))
+    }
+}
+
+impl<'a> Iterator for Networks<'_, 'a, Any> {
+    type Item = Result<(Network, Value<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_offset()?.and_then(|(network, mut offset)| {
+            Ok((
+                network,
+                read_value(self.reader.decoder_buffer, &mut offset)?,
+            ))
+        }))
+    }
 }
 
 #[reader(
@@ -173,6 +513,7 @@ impl<'a, T> Reader<'a, T> {
     "DBIP-Country-Lite"
 )]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Country<'a> {
     pub continent: Option<models::Continent<'a>>,
     pub country: Option<models::Country<'a>>,
@@ -183,6 +524,7 @@ pub struct Country<'a> {
 
 #[reader("GeoIP2-City", "GeoLite2-City", "DBIP-City-Lite")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct City<'a> {
     pub continent: Option<models::Continent<'a>>,
     pub country: Option<models::Country<'a>>,
@@ -197,6 +539,7 @@ pub struct City<'a> {
 
 #[reader("GeoIP2-Enterprise")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Enterprise<'a> {
     pub continent: Option<models::Continent<'a>>,
     pub country: Option<models::EnterpriseCountry<'a>>,
@@ -211,6 +554,7 @@ pub struct Enterprise<'a> {
 
 #[reader("GeoIP2-ISP")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct ISP<'a> {
     pub autonomous_system_number: Option<u32>,
     pub autonomous_system_organization: Option<&'a str>,
@@ -222,12 +566,14 @@ pub struct ISP<'a> {
 
 #[reader("GeoIP2-Connection-Type")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct ConnectionType<'a> {
     pub connection_type: Option<&'a str>,
 }
 
 #[reader("GeoIP2-Anonymous-IP")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct AnonymousIP {
     pub is_anonymous: Option<bool>,
     pub is_anonymous_vpn: Option<bool>,
@@ -239,6 +585,7 @@ pub struct AnonymousIP {
 
 #[reader("GeoLite2-ASN", "DBIP-ASN-Lite", "DBIP-ASN-Lite (compat=GeoLite2-ASN)")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct ASN<'a> {
     pub autonomous_system_number: Option<u32>,
     pub autonomous_system_organization: Option<&'a str>,
@@ -246,6 +593,7 @@ pub struct ASN<'a> {
 
 #[reader("GeoIP2-Domain")]
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", geoip2(serialize))]
 pub struct Domain<'a> {
     pub domain: Option<&'a str>,
 }
@@ -0,0 +1,53 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::errors::Error;
+use crate::owned::OwnedReader;
+use crate::reader::Record;
+
+/// A handle to an `OwnedReader` that can be replaced while it is in use.
+///
+/// Lookups keep the `Arc` returned by [`ReloadableReader::load`], so they
+/// finish on the old database while new lookups see the reloaded one.
+pub struct ReloadableReader<T: 'static> {
+    current: RwLock<Arc<OwnedReader<T>>>,
+}
+
+impl<T: Record + 'static> ReloadableReader<T> {
+    pub fn new(reader: OwnedReader<T>) -> ReloadableReader<T> {
+        ReloadableReader {
+            current: RwLock::new(Arc::new(reader)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<OwnedReader<T>> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Swaps in `reader` if it has the same database type and is not an older
+    /// build than the current one.
+    pub fn reload(&self, reader: OwnedReader<T>) -> Result<(), Error> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        {
+            let old = current.reader().get_metadata();
+            let new = reader.reader().get_metadata();
+            if new.database_type != old.database_type {
+                return Err(Error::InvalidDatabaseType(new.database_type.into()));
+            }
+            if new.build_epoch < old.build_epoch {
+                return Err(Error::InvalidBuildEpoch(new.build_epoch));
+            }
+        }
+        *current = Arc::new(reader);
+        Ok(())
+    }
+
+    pub fn reload_from_source<S>(&self, source: S) -> Result<(), Error>
+    where
+        S: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        self.reload(OwnedReader::from_source(source)?)
+    }
+}
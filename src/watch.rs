@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::errors::{Error, IoError};
+use crate::owned::OwnedReader;
+use crate::reader::Record;
+use crate::reload::ReloadableReader;
+
+/// Polls a database file and reloads a `ReloadableReader` when it changes.
+///
+/// Watching stops when the `Watcher` is dropped.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+fn file_version(path: &Path) -> Result<(SystemTime, u64), Error> {
+    let io_error = |err| Error::IoError(IoError::new(path, err));
+    let metadata = fs::metadata(path).map_err(io_error)?;
+    Ok((metadata.modified().map_err(io_error)?, metadata.len()))
+}
+
+impl<T: Record + Sync + 'static> ReloadableReader<T> {
+    /// Checks the modification time of `path` every `interval` and reloads it
+    /// when it changes, passing the outcome of every reload to `callback`.
+    pub fn watch<P, F>(
+        self: &Arc<Self>,
+        path: P,
+        interval: Duration,
+        mut callback: F,
+    ) -> Result<Watcher, Error>
+    where
+        P: Into<PathBuf>,
+        F: FnMut(Result<(), Error>) + Send + 'static,
+    {
+        let path = path.into();
+        let mut version = file_version(&path)?;
+        let reader = self.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || loop {
+                thread::park_timeout(interval);
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                // The file may be briefly missing while it is being replaced.
+                let current = match file_version(&path) {
+                    Ok(current) => current,
+                    Err(_) => continue,
+                };
+                if current == version {
+                    continue;
+                }
+                version = current;
+                callback(OwnedReader::open(&path).and_then(|new| reader.reload(new)));
+            })
+        };
+        Ok(Watcher {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
@@ -0,0 +1,658 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::decoder::{
+    Value, DATA_TYPE_BOOL, DATA_TYPE_BYTES, DATA_TYPE_FLOAT32, DATA_TYPE_FLOAT64, DATA_TYPE_INT32,
+    DATA_TYPE_MAP, DATA_TYPE_POINTER, DATA_TYPE_SLICE, DATA_TYPE_STRING, DATA_TYPE_UINT128,
+    DATA_TYPE_UINT16, DATA_TYPE_UINT32, DATA_TYPE_UINT64,
+};
+use crate::errors::Error;
+use crate::metadata::METADATA_START_MARKER;
+use crate::network::Network;
+
+const DATA_SECTION_SEPARATOR_SIZE: usize = 16;
+
+/// Networks left out of MaxMind databases, as they are private, reserved or
+/// for documentation, see RFC 6890.
+const RESERVED_IPV4: [([u8; 4], u8); 15] = [
+    ([0, 0, 0, 0], 8),
+    ([10, 0, 0, 0], 8),
+    ([100, 64, 0, 0], 10),
+    ([127, 0, 0, 0], 8),
+    ([169, 254, 0, 0], 16),
+    ([172, 16, 0, 0], 12),
+    ([192, 0, 0, 0], 29),
+    ([192, 0, 2, 0], 24),
+    ([192, 88, 99, 0], 24),
+    ([192, 168, 0, 0], 16),
+    ([198, 18, 0, 0], 15),
+    ([198, 51, 100, 0], 24),
+    ([203, 0, 113, 0], 24),
+    ([224, 0, 0, 0], 4),
+    ([240, 0, 0, 0], 4),
+];
+
+const RESERVED_IPV6: [(u128, u8); 5] = [
+    (0x0100 << 112, 64),
+    (0x2001_0db8 << 96, 32),
+    (0xfc00 << 112, 7),
+    (0xfe80 << 112, 10),
+    (0xff00 << 112, 8),
+];
+
+/// Teredo, 2001::/32, the start of the reserved 2001::/23.
+const TEREDO: (u128, u8) = (0x2001_0000 << 96, 32);
+
+/// Networks of an IPv6 database that lead to its IPv4 subtree: IPv4-mapped
+/// addresses, Teredo and 6to4.
+const IPV4_ALIASES: [(u128, u8); 3] = [(0xffff << 32, 96), TEREDO, (0x2002 << 112, 16)];
+
+/// How `Writer::insert_with` combines a value with the records already in
+/// its network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertStrategy {
+    /// The value replaces the records.
+    Replace,
+    /// The entries of the value replace the entries of the records with the
+    /// same key, other entries are kept.
+    ShallowMerge,
+    /// Like `ShallowMerge`, but maps under the same key are merged too.
+    DeepMerge,
+}
+
+#[derive(Clone, PartialEq)]
+enum Node {
+    Empty,
+    /// An index into `Writer::values`.
+    Data(usize),
+    /// The IPv4 subtree, only used while writing.
+    Alias,
+    Branch(Box<[Node; 2]>),
+}
+
+/// Builds a MaxMind DB database.
+///
+/// Values are written once to the data section, values repeated within or
+/// across records are replaced with pointers.
+pub struct Writer<'a> {
+    pub database_type: &'a str,
+    pub record_size: u16,
+    pub languages: Vec<&'a str>,
+    /// Must not be empty, `[("en", database_type)]` by default.
+    pub description: Vec<(&'a str, &'a str)>,
+    /// Seconds since the Unix epoch, the time of `Writer::new` by default.
+    pub build_epoch: u64,
+    /// Keeps the reserved networks, which are left empty by default.
+    pub include_reserved_networks: bool,
+    /// Makes the IPv4 aliases of an IPv6 database lead to its IPv4 subtree,
+    /// on by default.
+    pub ipv4_aliases: bool,
+    ip_version: u16,
+    root: Node,
+    values: Vec<Value<'a>>,
+    indexes: HashMap<Vec<u8>, usize>,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(database_type: &'a str, ip_version: u16) -> Result<Writer<'a>, Error> {
+        if ip_version != 4 && ip_version != 6 {
+            return Err(Error::InvalidMetadataField("ip_version"));
+        }
+        Ok(Writer {
+            database_type,
+            record_size: 28,
+            languages: Vec::new(),
+            description: vec![("en", database_type)],
+            build_epoch: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            include_reserved_networks: false,
+            ipv4_aliases: true,
+            ip_version,
+            root: Node::Empty,
+            values: Vec::new(),
+            indexes: HashMap::new(),
+        })
+    }
+
+    fn bit_count(&self) -> usize {
+        if self.ip_version == 4 {
+            32
+        } else {
+            128
+        }
+    }
+
+    /// Sets the record of every address in `network`, replacing the records
+    /// of any networks it contains.
+    pub fn insert(&mut self, network: Network, value: Value<'a>) -> Result<(), Error> {
+        self.insert_with(network, value, InsertStrategy::Replace)
+    }
+
+    /// Sets the record of every address in `network`, combined with the
+    /// records already there as `strategy` says.
+    pub fn insert_with(
+        &mut self,
+        network: Network,
+        value: Value<'a>,
+        strategy: InsertStrategy,
+    ) -> Result<(), Error> {
+        let prefix_len = network.prefix_len() as usize;
+        let (ip, prefix_len) = match network.address() {
+            IpAddr::V4(ip) if self.ip_version == 4 => (u32::from(ip) as u128, prefix_len),
+            IpAddr::V4(ip) => (u32::from(ip) as u128, prefix_len + 96),
+            IpAddr::V6(_) if self.ip_version == 4 => return Err(Error::IPv4Only),
+            IpAddr::V6(ip) => (u128::from(ip), prefix_len),
+        };
+        let reserved = self.reserved_networks();
+        let aliases = self.aliased_networks();
+        if reserved
+            .iter()
+            .chain(&aliases)
+            .any(|&within| contains(within, (ip, prefix_len), self.bit_count()))
+        {
+            return Err(Error::ReservedNetwork(network));
+        }
+
+        // Taken out of `self` while values are added to it.
+        let mut root = std::mem::replace(&mut self.root, Node::Empty);
+        let result = self.insert_into(&mut root, ip, prefix_len, value, strategy);
+        self.root = root;
+        result
+    }
+
+    fn insert_into(
+        &mut self,
+        root: &mut Node,
+        ip: u128,
+        prefix_len: usize,
+        value: Value<'a>,
+        strategy: InsertStrategy,
+    ) -> Result<(), Error> {
+        let bit_count = self.bit_count();
+        if strategy == InsertStrategy::Replace {
+            let index = self.value_index(value)?;
+            update(root, ip, bit_count, 0, prefix_len, &mut |node| {
+                *node = Node::Data(index)
+            });
+            return Ok(());
+        }
+        // Every merged value is added before the tree is changed, so a value
+        // that can't be encoded leaves it as it was.
+        let deep = strategy == InsertStrategy::DeepMerge;
+        let mut merged = HashMap::new();
+        let mut leaves = Vec::new();
+        for_each_index(get(root, ip, bit_count, prefix_len), &mut |index| {
+            leaves.push(index)
+        });
+        for index in leaves {
+            if merged.contains_key(&index) {
+                continue;
+            }
+            let value = match index {
+                Some(index) => merge(&self.values[index], &value, deep),
+                None => value.clone(),
+            };
+            merged.insert(index, self.value_index(value)?);
+        }
+        let mut merge_leaf = |leaf: &mut Node| {
+            let index = match leaf {
+                Node::Data(index) => Some(*index),
+                _ => None,
+            };
+            *leaf = Node::Data(merged[&index]);
+        };
+        update(root, ip, bit_count, 0, prefix_len, &mut |node| {
+            for_each_leaf(node, &mut merge_leaf)
+        });
+        Ok(())
+    }
+
+    /// Returns the reserved networks to leave empty, as paths in the tree.
+    fn reserved_networks(&self) -> Vec<(u128, usize)> {
+        if self.include_reserved_networks {
+            return Vec::new();
+        }
+        let ipv4_depth = if self.ip_version == 4 { 0 } else { 96 };
+        let mut networks = RESERVED_IPV4
+            .iter()
+            .map(|&(ip, prefix_len)| {
+                (
+                    u32::from_be_bytes(ip) as u128,
+                    prefix_len as usize + ipv4_depth,
+                )
+            })
+            .collect::<Vec<_>>();
+        if self.ip_version == 6 {
+            networks.extend(
+                RESERVED_IPV6
+                    .iter()
+                    .map(|&(ip, prefix_len)| (ip, prefix_len as usize)),
+            );
+            // 2001::/23 but Teredo, as the halves of it that don't start with
+            // Teredo's /32.
+            let (teredo, teredo_len) = TEREDO;
+            networks.extend(
+                (24..=teredo_len as usize)
+                    .map(|prefix_len| (teredo | 1 << (128 - prefix_len), prefix_len)),
+            );
+        }
+        networks
+    }
+
+    fn aliased_networks(&self) -> Vec<(u128, usize)> {
+        if self.ip_version == 4 || !self.ipv4_aliases {
+            return Vec::new();
+        }
+        IPV4_ALIASES
+            .iter()
+            .map(|&(ip, prefix_len)| (ip, prefix_len as usize))
+            .collect()
+    }
+
+    /// Returns the index of `value` in `values`, so equal records share a
+    /// leaf and sibling leaves can be merged.
+    fn value_index(&mut self, value: Value<'a>) -> Result<usize, Error> {
+        let mut key = Vec::new();
+        encode(&value, &mut key)?;
+        Ok(*self.indexes.entry(key).or_insert_with(|| {
+            self.values.push(value);
+            self.values.len() - 1
+        }))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        if self.record_size != 24 && self.record_size != 28 && self.record_size != 32 {
+            return Err(Error::InvalidRecordSize(self.record_size));
+        }
+        if self.description.is_empty() {
+            return Err(Error::InvalidMetadataField("description"));
+        }
+        let bit_count = self.bit_count();
+        let mut root = self.root.clone();
+        for (ip, prefix_len) in self.reserved_networks() {
+            update(&mut root, ip, bit_count, 0, prefix_len, &mut |node| {
+                *node = Node::Empty
+            });
+        }
+        let alias = match get(&root, 0, bit_count, 96) {
+            Node::Branch(_) => Node::Alias,
+            leaf => leaf.clone(),
+        };
+        for (ip, prefix_len) in self.aliased_networks() {
+            update(&mut root, ip, bit_count, 0, prefix_len, &mut |node| {
+                *node = alias.clone()
+            });
+        }
+        // A tree needs at least one node, even if its root is a leaf.
+        let root = match root {
+            Node::Branch(children) => children,
+            leaf => Box::new([leaf.clone(), leaf]),
+        };
+        // ::/96 is 95 left children below the left child of the root.
+        let ipv4_subtree = match get(&root[0], 0, bit_count, 95) {
+            Node::Branch(children) if self.ip_version == 6 => Some(&**children),
+            _ => None,
+        };
+
+        // Numbers the nodes breadth first, so the root is node 0, and writes
+        // the records their leaves point to.
+        let mut data = DataSection::default();
+        let mut record_offsets = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut ipv4_node = 0;
+        let mut queue = VecDeque::from([&root]);
+        while let Some(children) = queue.pop_front() {
+            let mut records = [Record::Empty; 2];
+            for (record, child) in records.iter_mut().zip(children.iter()) {
+                *record = match child {
+                    Node::Empty => Record::Empty,
+                    Node::Data(index) => Record::Data(match record_offsets.get(index) {
+                        Some(&offset) => offset,
+                        None => {
+                            let offset = data.write_record(&self.values[*index])?;
+                            record_offsets.insert(*index, offset);
+                            offset
+                        }
+                    }),
+                    Node::Alias => Record::Alias,
+                    Node::Branch(children) => {
+                        queue.push_back(children);
+                        let node = nodes.len() + queue.len();
+                        if ipv4_subtree.is_some_and(|v| std::ptr::eq(v, &**children)) {
+                            ipv4_node = node;
+                        }
+                        Record::Node(node)
+                    }
+                };
+            }
+            nodes.push(records);
+        }
+
+        let node_count = nodes.len();
+        let record_bytes = self.record_size as usize / 4;
+        let mut buffer = Vec::with_capacity(
+            node_count * record_bytes + DATA_SECTION_SEPARATOR_SIZE + data.buffer.len(),
+        );
+        for [left, right] in nodes {
+            let left = left.value(node_count, ipv4_node);
+            let right = right.value(node_count, ipv4_node);
+            if left.max(right) >> self.record_size != 0 {
+                return Err(Error::RecordSizeTooSmall(self.record_size));
+            }
+            match self.record_size {
+                24 => {
+                    buffer.extend_from_slice(&(left as u32).to_be_bytes()[1..]);
+                    buffer.extend_from_slice(&(right as u32).to_be_bytes()[1..]);
+                }
+                28 => {
+                    buffer.extend_from_slice(&(left as u32).to_be_bytes()[1..]);
+                    buffer.push((((left >> 24) as u8) << 4) | (right >> 24) as u8);
+                    buffer.extend_from_slice(&(right as u32).to_be_bytes()[1..]);
+                }
+                _ => {
+                    buffer.extend_from_slice(&(left as u32).to_be_bytes());
+                    buffer.extend_from_slice(&(right as u32).to_be_bytes());
+                }
+            }
+        }
+        buffer.extend_from_slice(&[0; DATA_SECTION_SEPARATOR_SIZE]);
+        buffer.extend_from_slice(&data.buffer);
+        buffer.extend_from_slice(&METADATA_START_MARKER);
+        encode(&self.metadata(node_count as u32), &mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn metadata(&self, node_count: u32) -> Value<'a> {
+        Value::Map(vec![
+            ("binary_format_major_version", Value::Uint16(2)),
+            ("binary_format_minor_version", Value::Uint16(0)),
+            ("build_epoch", Value::Uint64(self.build_epoch)),
+            ("database_type", Value::String(self.database_type)),
+            (
+                "description",
+                Value::Map(
+                    self.description
+                        .iter()
+                        .map(|&(language, text)| (language, Value::String(text)))
+                        .collect(),
+                ),
+            ),
+            ("ip_version", Value::Uint16(self.ip_version)),
+            (
+                "languages",
+                Value::Array(self.languages.iter().map(|&v| Value::String(v)).collect()),
+            ),
+            ("node_count", Value::Uint32(node_count)),
+            ("record_size", Value::Uint16(self.record_size)),
+        ])
+    }
+}
+
+/// Calls `f` on the subtree of `ip/prefix_len`, splitting the leaves above it,
+/// then merges the leaves that became equal.
+fn update(
+    node: &mut Node,
+    ip: u128,
+    bit_count: usize,
+    depth: usize,
+    prefix_len: usize,
+    f: &mut dyn FnMut(&mut Node),
+) {
+    if depth == prefix_len {
+        f(node);
+        return;
+    }
+    if !matches!(node, Node::Branch(_)) {
+        // The rest of the network this leaf covered keeps its record.
+        *node = Node::Branch(Box::new([node.clone(), node.clone()]));
+    }
+    if let Node::Branch(children) = node {
+        let bit = (ip >> (bit_count - depth - 1)) & 1;
+        update(
+            &mut children[bit as usize],
+            ip,
+            bit_count,
+            depth + 1,
+            prefix_len,
+            f,
+        );
+        merge_children(node);
+    }
+}
+
+fn for_each_leaf(node: &mut Node, f: &mut dyn FnMut(&mut Node)) {
+    if let Node::Branch(children) = node {
+        for child in children.iter_mut() {
+            for_each_leaf(child, f);
+        }
+        merge_children(node);
+    } else {
+        f(node);
+    }
+}
+
+/// Calls `f` with the index of every leaf below `node`, `None` if empty.
+fn for_each_index(node: &Node, f: &mut dyn FnMut(Option<usize>)) {
+    match node {
+        Node::Branch(children) => {
+            for child in children.iter() {
+                for_each_index(child, f);
+            }
+        }
+        Node::Data(index) => f(Some(*index)),
+        _ => f(None),
+    }
+}
+
+fn merge_children(node: &mut Node) {
+    if let Node::Branch(children) = node {
+        if children[0] == children[1] && !matches!(children[0], Node::Branch(_)) {
+            *node = children[0].clone();
+        }
+    }
+}
+
+/// Returns the node of `ip/prefix_len`, or the leaf above it that covers it.
+fn get(mut node: &Node, ip: u128, bit_count: usize, prefix_len: usize) -> &Node {
+    for depth in 0..prefix_len {
+        match node {
+            Node::Branch(children) => {
+                node = &children[((ip >> (bit_count - depth - 1)) & 1) as usize]
+            }
+            _ => break,
+        }
+    }
+    node
+}
+
+/// Whether the network `within` contains `network`, both as paths in the tree.
+fn contains(within: (u128, usize), network: (u128, usize), bit_count: usize) -> bool {
+    network.1 >= within.1
+        && (within.0 ^ network.0)
+            .checked_shr((bit_count - within.1) as u32)
+            .unwrap_or(0)
+            == 0
+}
+
+fn merge<'a>(old: &Value<'a>, new: &Value<'a>, deep: bool) -> Value<'a> {
+    match (old, new) {
+        (Value::Map(old), Value::Map(new)) => {
+            let mut map = old.clone();
+            for (key, value) in new {
+                match map.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, old)) if deep => *old = merge(old, value, deep),
+                    Some((_, old)) => *old = value.clone(),
+                    None => map.push((key, value.clone())),
+                }
+            }
+            Value::Map(map)
+        }
+        _ => new.clone(),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Record {
+    Empty,
+    Node(usize),
+    /// An offset in the data section.
+    Data(usize),
+    Alias,
+}
+
+impl Record {
+    fn value(self, node_count: usize, ipv4_node: usize) -> usize {
+        match self {
+            Record::Alias => ipv4_node,
+            Record::Empty => node_count,
+            Record::Node(node) => node,
+            Record::Data(offset) => node_count + DATA_SECTION_SEPARATOR_SIZE + offset,
+        }
+    }
+}
+
+#[derive(Default)]
+struct DataSection {
+    buffer: Vec<u8>,
+    /// The offset every value written so far was first written at, by its
+    /// encoding without pointers.
+    offsets: HashMap<Vec<u8>, usize>,
+}
+
+impl DataSection {
+    /// Writes a record the search tree points to, which can't be a pointer
+    /// itself.
+    fn write_record(&mut self, value: &Value) -> Result<usize, Error> {
+        let mut key = Vec::new();
+        encode(value, &mut key)?;
+        let offset = self.buffer.len();
+        self.write_contents(value)?;
+        self.offsets.entry(key).or_insert(offset);
+        Ok(offset)
+    }
+
+    fn write(&mut self, value: &Value) -> Result<(), Error> {
+        let mut key = Vec::new();
+        encode(value, &mut key)?;
+        if let Some(&offset) = self.offsets.get(&key) {
+            let mut pointer = Vec::new();
+            if encode_pointer(offset, &mut pointer) && pointer.len() < key.len() {
+                self.buffer.extend_from_slice(&pointer);
+                return Ok(());
+            }
+        }
+        let offset = self.buffer.len();
+        self.write_contents(value)?;
+        self.offsets.entry(key).or_insert(offset);
+        Ok(())
+    }
+
+    fn write_contents(&mut self, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::Map(map) => {
+                encode_control(DATA_TYPE_MAP, map.len(), &mut self.buffer)?;
+                for (key, value) in map {
+                    self.write(&Value::String(key))?;
+                    self.write(value)?;
+                }
+                Ok(())
+            }
+            Value::Array(array) => {
+                encode_control(DATA_TYPE_SLICE, array.len(), &mut self.buffer)?;
+                for value in array {
+                    self.write(value)?;
+                }
+                Ok(())
+            }
+            _ => encode(value, &mut self.buffer),
+        }
+    }
+}
+
+/// Encodes `value` in full, without pointers.
+fn encode(value: &Value, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    match value {
+        Value::Map(map) => {
+            encode_control(DATA_TYPE_MAP, map.len(), buffer)?;
+            for (key, value) in map {
+                encode(&Value::String(key), buffer)?;
+                encode(value, buffer)?;
+            }
+            Ok(())
+        }
+        Value::Array(array) => {
+            encode_control(DATA_TYPE_SLICE, array.len(), buffer)?;
+            for value in array {
+                encode(value, buffer)?;
+            }
+            Ok(())
+        }
+        Value::String(value) => encode_bytes(DATA_TYPE_STRING, value.as_bytes(), buffer),
+        Value::Bytes(value) => encode_bytes(DATA_TYPE_BYTES, value, buffer),
+        Value::Double(value) => encode_bytes(DATA_TYPE_FLOAT64, &value.to_be_bytes(), buffer),
+        Value::Float(value) => encode_bytes(DATA_TYPE_FLOAT32, &value.to_be_bytes(), buffer),
+        Value::Uint16(value) => encode_uint(DATA_TYPE_UINT16, &value.to_be_bytes(), buffer),
+        Value::Uint32(value) => encode_uint(DATA_TYPE_UINT32, &value.to_be_bytes(), buffer),
+        Value::Uint64(value) => encode_uint(DATA_TYPE_UINT64, &value.to_be_bytes(), buffer),
+        Value::Uint128(value) => encode_uint(DATA_TYPE_UINT128, &value.to_be_bytes(), buffer),
+        // Leading zeros only ever come from non-negative values, so they can
+        // be dropped the same way.
+        Value::Int32(value) => encode_uint(DATA_TYPE_INT32, &value.to_be_bytes(), buffer),
+        Value::Bool(value) => encode_control(DATA_TYPE_BOOL, *value as usize, buffer),
+    }
+}
+
+fn encode_bytes(data_type: u8, bytes: &[u8], buffer: &mut Vec<u8>) -> Result<(), Error> {
+    encode_control(data_type, bytes.len(), buffer)?;
+    buffer.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Encodes an unsigned integer without its leading zero bytes.
+fn encode_uint(data_type: u8, bytes: &[u8], buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    encode_bytes(data_type, &bytes[zeros..], buffer)
+}
+
+/// The largest size a control byte can hold, with three extra size bytes.
+const MAX_SIZE: usize = 65_821 + 0xff_ffff;
+
+fn encode_control(data_type: u8, size: usize, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let (size_bits, extra) = match size {
+        0..=28 => (size as u8, &[][..]),
+        29..=284 => (29, &((size - 29) as u32).to_be_bytes()[3..]),
+        285..=65_820 => (30, &((size - 285) as u32).to_be_bytes()[2..]),
+        65_821..=MAX_SIZE => (31, &((size - 65_821) as u32).to_be_bytes()[1..]),
+        _ => return Err(Error::DataTooLarge { data_type, size }),
+    };
+    if data_type > 7 {
+        buffer.push(size_bits);
+        buffer.push(data_type - 7);
+    } else {
+        buffer.push((data_type << 5) | size_bits);
+    }
+    buffer.extend_from_slice(extra);
+    Ok(())
+}
+
+/// Returns `false` without writing anything if `offset` doesn't fit in a
+/// pointer.
+fn encode_pointer(offset: usize, buffer: &mut Vec<u8>) -> bool {
+    let (size, value) = match offset {
+        0..=2_047 => (0, offset),
+        2_048..=526_335 => (1, offset - 2_048),
+        526_336..=134_744_063 => (2, offset - 526_336),
+        134_744_064..=0xffff_ffff => (3, offset),
+        _ => return false,
+    };
+    let bytes = (value as u32).to_be_bytes();
+    let prefix = match size {
+        3 => 0,
+        _ => (value >> ((size + 1) * 8)) as u8,
+    };
+    buffer.push((DATA_TYPE_POINTER << 5) | ((size as u8) << 3) | prefix);
+    buffer.extend_from_slice(&bytes[3 - size..]);
+    true
+}
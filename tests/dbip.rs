@@ -15,7 +15,7 @@ mod tests {
         let city = result.city.unwrap();
         assert_eq!(city.geoname_id, None);
         let names = city.names.unwrap();
-        assert_eq!(names.get("en"), Some("Medfield"));
+        assert_eq!(names.get("en"), Ok(Some("Medfield")));
 
         let location = result.location.unwrap();
         assert_eq!(location.latitude, Some(42.1876));
@@ -25,7 +25,7 @@ mod tests {
         assert_eq!(subdivisions.len(), 1);
         let subdivision = &subdivisions[0];
         let names = subdivision.names.as_ref().unwrap();
-        assert_eq!(names.get("en"), Some("Massachusetts"));
+        assert_eq!(names.get("en"), Ok(Some("Massachusetts")));
     }
 
     #[test]
@@ -40,15 +40,15 @@ mod tests {
         assert_eq!(continent.geoname_id, Some(6255149));
         assert_eq!(continent.code, Some("NA"));
         let names = continent.names.unwrap();
-        assert_eq!(names.get("en"), Some("North America"));
-        assert_eq!(names.get("ru"), Some("Северная Америка"));
+        assert_eq!(names.get("en"), Ok(Some("North America")));
+        assert_eq!(names.get("ru"), Ok(Some("Северная Америка")));
 
         let country = result.country.unwrap();
         assert_eq!(country.geoname_id, Some(6252001));
         assert_eq!(country.iso_code, Some("US"));
         let names = country.names.unwrap();
-        assert_eq!(names.get("fr"), Some("États-Unis"));
-        assert_eq!(names.get("pt-BR"), Some("Estados Unidos"));
+        assert_eq!(names.get("fr"), Ok(Some("États-Unis")));
+        assert_eq!(names.get("pt-BR"), Ok(Some("Estados Unidos")));
         assert_eq!(country.is_in_european_union, Some(false));
     }
 
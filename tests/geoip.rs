@@ -1,9 +1,11 @@
 #[cfg(test)]
 mod tests {
     use geoip2::{
-        AnonymousIP, City, ConnectionType, Country, Domain, Enterprise, Error, Reader, ASN, ISP,
+        AnonymousIP, Any, City, ConnectionType, Country, Decoder, Domain, Enterprise, Error,
+        InsertStrategy, Names, Network, OwnedReader, Reader, ReloadableReader, Value, Writer, ASN,
+        ISP,
     };
-    use std::{net::IpAddr, str::FromStr};
+    use std::{net::IpAddr, str::FromStr, sync::Arc};
 
     #[test]
     fn test_invalid_database_type() {
@@ -108,8 +110,16 @@ mod tests {
             let city = result.city.unwrap();
             assert_eq!(city.geoname_id, Some(2643743));
             let names = city.names.unwrap();
-            assert_eq!(names.get("de"), Some("London"));
-            assert_eq!(names.get("es"), Some("Londres"));
+            assert_eq!(names.get("de"), Ok(Some("London")));
+            assert_eq!(names.get("es"), Ok(Some("Londres")));
+            assert_eq!(names.get("xx"), Ok(None));
+            assert_eq!(names.len(), 7);
+            assert_eq!(names.iter().count(), 7);
+            assert_eq!(
+                names.iter().next(),
+                Some(Ok(("de", Value::String("London"))))
+            );
+            assert!(format!("{:?}", names).contains("\"es\": \"Londres\""));
 
             let location = result.location.unwrap();
             assert_eq!(location.accuracy_radius, Some(10));
@@ -123,8 +133,8 @@ mod tests {
             assert_eq!(subdivision.geoname_id, Some(6269131));
             assert_eq!(subdivision.iso_code, Some("ENG"));
             let names = subdivision.names.as_ref().unwrap();
-            assert_eq!(names.get("en"), Some("England"));
-            assert_eq!(names.get("pt-BR"), Some("Inglaterra"));
+            assert_eq!(names.get("en"), Ok(Some("England")));
+            assert_eq!(names.get("pt-BR"), Ok(Some("Inglaterra")));
         }
         {
             let result = reader
@@ -146,6 +156,1417 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_locales() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let result = reader
+            .lookup(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        assert_eq!(reader.get_metadata().languages, vec!["en", "zh"]);
+        // None of the names are in "zh", only in "zh-CN".
+        let locales = reader.locales(&["zh", "en"]).unwrap();
+        assert_eq!(
+            result.names(&locales),
+            Ok(Names {
+                continent: Some("Europe"),
+                country: Some("United Kingdom"),
+                subdivisions: vec![Some("England")],
+                city: Some("London"),
+                represented_country: None,
+            })
+        );
+        // Subdivisions without a name keep their position.
+        let locales = reader.locales(&["zh"]).unwrap();
+        assert_eq!(
+            result.names(&locales),
+            Ok(Names {
+                subdivisions: vec![None],
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            reader.locales(&["en", "pt-BR"]).unwrap_err(),
+            Error::UnknownLanguage("pt-BR".into())
+        );
+    }
+
+    #[test]
+    fn test_lookup_network() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        {
+            let (result, network) = reader
+                .lookup_network(IpAddr::from_str("81.2.69.142").unwrap())
+                .unwrap();
+            assert_eq!(result.city.unwrap().geoname_id, Some(2643743));
+            assert_eq!(network.address(), IpAddr::from_str("81.2.69.142").unwrap());
+            assert_eq!(network.prefix_len(), 31);
+            assert_eq!(network.to_string(), "81.2.69.142/31");
+        }
+        {
+            let (_, network) = reader
+                .lookup_network(IpAddr::from_str("89.160.20.120").unwrap())
+                .unwrap();
+            assert_eq!(network.to_string(), "89.160.20.112/28");
+            assert!(network.contains(IpAddr::from_str("89.160.20.127").unwrap()));
+            assert!(!network.contains(IpAddr::from_str("89.160.20.128").unwrap()));
+        }
+        {
+            let (_, network) = reader
+                .lookup_network(IpAddr::from_str("2001:218::1").unwrap())
+                .unwrap();
+            assert_eq!(
+                network,
+                Network::new(IpAddr::from_str("2001:218::").unwrap(), 32).unwrap()
+            );
+        }
+        assert_eq!(
+            Network::new(IpAddr::from_str("10.0.0.0").unwrap(), 33),
+            Err(Error::InvalidPrefixLength(33))
+        );
+    }
+
+    #[test]
+    fn test_networks() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let mut networks = Vec::new();
+        for item in reader.networks() {
+            let (network, result) = item.unwrap();
+            let (expected, expected_network) = reader.lookup_network(network.address()).unwrap();
+            assert_eq!(network, expected_network);
+            assert_eq!(
+                result.city.map(|city| city.geoname_id),
+                expected.city.map(|city| city.geoname_id)
+            );
+            networks.push(network);
+        }
+        assert_eq!(networks.len(), 242);
+        assert_eq!(networks[0].to_string(), "2.2.3.0/24");
+        assert_eq!(
+            reader
+                .networks()
+                .without_records()
+                .collect::<Result<Vec<_>, _>>(),
+            Ok(networks.clone())
+        );
+        let count = networks.len();
+        networks.sort_by_key(|network| network.to_string());
+        networks.dedup();
+        assert_eq!(networks.len(), count);
+        assert!(networks
+            .iter()
+            .any(|network| network.to_string() == "81.2.69.142/31"));
+        assert!(!networks
+            .iter()
+            .any(|network| network.to_string().starts_with("::ffff:")));
+    }
+
+    #[test]
+    fn test_within() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        {
+            let networks: Vec<String> = reader
+                .within("81.2.69.0/24".parse().unwrap())
+                .unwrap()
+                .map(|item| item.unwrap().0.to_string())
+                .collect();
+            assert_eq!(
+                networks,
+                vec![
+                    "81.2.69.142/31",
+                    "81.2.69.144/28",
+                    "81.2.69.160/27",
+                    "81.2.69.192/28"
+                ]
+            );
+        }
+        {
+            let (network, result) = reader
+                .within("2.125.160.218/32".parse().unwrap())
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap();
+            assert_eq!(network.to_string(), "2.125.160.216/29");
+            assert_eq!(result.city.unwrap().geoname_id, Some(2655045));
+        }
+        assert_eq!(
+            reader
+                .within("2001:218::/16".parse().unwrap())
+                .unwrap()
+                .count(),
+            29
+        );
+        assert_eq!(reader.within("::/0".parse().unwrap()).unwrap().count(), 242);
+        assert_eq!(
+            reader
+                .within("10.0.0.0/8".parse().unwrap())
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(
+            "10.0.0.0".parse::<Network>(),
+            Err(Error::InvalidNetwork("10.0.0.0".into()))
+        );
+    }
+
+    #[test]
+    fn test_owned_reader() {
+        struct State {
+            reader: OwnedReader<City<'static>>,
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let state = Arc::new(State {
+            reader: OwnedReader::from_source(buffer).unwrap(),
+        });
+        let handle = {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                let result = state
+                    .reader
+                    .reader()
+                    .lookup(IpAddr::from_str("81.2.69.142").unwrap())
+                    .unwrap();
+                result.city.unwrap().geoname_id
+            })
+        };
+        assert_eq!(handle.join().unwrap(), Some(2643743));
+
+        let reader = state.reader.reader();
+        let result = reader
+            .lookup(IpAddr::from_str("2a02:ff80::").unwrap())
+            .unwrap();
+        assert_eq!(result.location.unwrap().time_zone, Some("Europe/Berlin"));
+        assert_eq!(reader.get_metadata().database_type, "GeoIP2-City");
+
+        let buffer: Arc<[u8]> = std::fs::read("./testdata/GeoIP2-Country-Test.mmdb")
+            .unwrap()
+            .into();
+        let reader = OwnedReader::<City>::from_source(buffer);
+        if let Err(Error::InvalidDatabaseType(msg)) = reader {
+            assert_eq!(msg, "GeoIP2-Country");
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn test_open() {
+        let err = match OwnedReader::<City>::open("./testdata/missing.mmdb") {
+            Err(Error::IoError(err)) => err,
+            _ => panic!("expected an i/o error"),
+        };
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(
+            err.path(),
+            Some(std::path::Path::new("./testdata/missing.mmdb"))
+        );
+        let err = Error::IoError(err);
+        assert!(err.to_string().starts_with("./testdata/missing.mmdb: "));
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_mmap() {
+        let reader = OwnedReader::<City>::open_mmap("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let result = reader
+            .reader()
+            .lookup(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        assert_eq!(result.city.unwrap().geoname_id, Some(2643743));
+
+        let reader = OwnedReader::<City>::open_mmap("./testdata/missing.mmdb");
+        assert!(matches!(
+            reader,
+            Err(Error::IoError(ref err)) if err.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_reloadable_reader() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let epoch_offset = buffer
+            .windows(11)
+            .rposition(|window| window == b"build_epoch")
+            .unwrap()
+            + 13;
+        let reader = ReloadableReader::new(
+            OwnedReader::<City>::open("./testdata/GeoIP2-City-Test.mmdb").unwrap(),
+        );
+        let old = reader.load();
+
+        let mut newer = buffer.clone();
+        newer[epoch_offset] += 1;
+        reader.reload_from_source(newer).unwrap();
+        let new = reader.load();
+        assert_eq!(
+            new.reader().get_metadata().build_epoch,
+            old.reader().get_metadata().build_epoch + (1 << 24)
+        );
+        let result = old
+            .reader()
+            .lookup(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        assert_eq!(result.city.unwrap().geoname_id, Some(2643743));
+
+        assert_eq!(
+            reader.reload_from_source(buffer),
+            Err(Error::InvalidBuildEpoch(
+                old.reader().get_metadata().build_epoch
+            ))
+        );
+        assert_eq!(
+            reader.reload(OwnedReader::open("./testdata/GeoLite2-City-Test.mmdb").unwrap()),
+            Err(Error::InvalidDatabaseType("GeoLite2-City".into()))
+        );
+        assert!(Arc::ptr_eq(&new, &reader.load()));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_watch() {
+        use std::time::{Duration, SystemTime};
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let epoch_offset = buffer
+            .windows(11)
+            .rposition(|window| window == b"build_epoch")
+            .unwrap()
+            + 13;
+        let dir = std::env::temp_dir().join(format!("geoip2-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("GeoIP2-City-Test.mmdb");
+        std::fs::write(&path, &buffer).unwrap();
+        let replace = |contents: &[u8], modified: SystemTime| {
+            let tmp = dir.join("GeoIP2-City-Test.mmdb.tmp");
+            std::fs::write(&tmp, contents).unwrap();
+            let file = std::fs::File::options().write(true).open(&tmp).unwrap();
+            file.set_modified(modified).unwrap();
+            std::fs::rename(&tmp, &path).unwrap();
+        };
+
+        let reader = Arc::new(ReloadableReader::new(
+            OwnedReader::<City>::open(&path).unwrap(),
+        ));
+        let old_epoch = reader.load().reader().get_metadata().build_epoch;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let watcher = reader
+            .watch(&path, Duration::from_millis(10), move |result| {
+                sender.send(result).unwrap();
+            })
+            .unwrap();
+
+        let mut newer = buffer.clone();
+        newer[epoch_offset] += 1;
+        replace(&newer, SystemTime::now() + Duration::from_secs(10));
+        let result = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(result, Ok(()));
+        let epoch = reader.load().reader().get_metadata().build_epoch;
+        assert_eq!(epoch, old_epoch + (1 << 24));
+
+        replace(&buffer[..1024], SystemTime::now() + Duration::from_secs(20));
+        let result = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(result, Err(Error::InvalidMetadata));
+        assert_eq!(reader.load().reader().get_metadata().build_epoch, epoch);
+
+        drop(watcher);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lookup_as() {
+        use serde::Deserialize;
+        use std::collections::BTreeMap;
+
+        #[derive(Deserialize)]
+        struct Names<'a> {
+            #[serde(borrow)]
+            names: BTreeMap<&'a str, &'a str>,
+        }
+
+        #[derive(Deserialize)]
+        struct Location<'a> {
+            latitude: f64,
+            longitude: f64,
+            time_zone: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct MyCity<'a> {
+            #[serde(borrow)]
+            city: Option<Names<'a>>,
+            #[serde(borrow)]
+            location: Location<'a>,
+            #[serde(borrow)]
+            subdivisions: Vec<Names<'a>>,
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let result: MyCity = reader
+            .lookup_as(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        assert_eq!(result.city.unwrap().names.get("es"), Some(&"Londres"));
+        assert_eq!(result.location.latitude, 51.5142);
+        assert_eq!(result.location.longitude, -0.0931);
+        assert_eq!(result.location.time_zone, "Europe/London");
+        assert_eq!(result.subdivisions[0].names.get("en"), Some(&"England"));
+
+        let result = reader.lookup_as::<MyCity>(IpAddr::from_str("2a02:ff80::").unwrap());
+        assert!(matches!(result, Err(Error::DeserializeError(_))));
+    }
+
+    #[test]
+    fn test_lookup_value() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let result = reader
+            .lookup_value(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        let city = result.get("city").unwrap();
+        assert_eq!(city.get("geoname_id"), Some(&Value::Uint32(2643743)));
+        assert_eq!(
+            city.get("names").unwrap().get("es"),
+            Some(&Value::String("Londres"))
+        );
+        let location = result.get("location").unwrap();
+        assert_eq!(location.get("latitude"), Some(&Value::Double(51.5142)));
+        assert_eq!(location.get("accuracy_radius"), Some(&Value::Uint16(10)));
+        if let Some(Value::Array(subdivisions)) = result.get("subdivisions") {
+            assert_eq!(subdivisions[0].get("iso_code"), Some(&Value::String("ENG")));
+        } else {
+            panic!();
+        }
+        assert_eq!(result.get("missing"), None);
+    }
+
+    #[test]
+    fn test_lookup_path() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let ip = IpAddr::from_str("81.2.69.142").unwrap();
+        assert_eq!(
+            reader.lookup_path(ip, &["country", "iso_code"]).unwrap(),
+            Some(Value::String("GB"))
+        );
+        assert_eq!(
+            reader.lookup_path(ip, &["city", "names", "es"]).unwrap(),
+            Some(Value::String("Londres"))
+        );
+        assert_eq!(
+            reader
+                .lookup_path(ip, &["subdivisions", "0", "iso_code"])
+                .unwrap(),
+            Some(Value::String("ENG"))
+        );
+        assert_eq!(
+            reader.lookup_path(ip, &["location"]).unwrap(),
+            reader.lookup_value(ip).unwrap().get("location").cloned()
+        );
+        assert_eq!(
+            reader.lookup_path(ip, &[]).unwrap(),
+            Some(reader.lookup_value(ip).unwrap())
+        );
+        assert_eq!(reader.lookup_path(ip, &["missing"]).unwrap(), None);
+        assert_eq!(
+            reader.lookup_path(ip, &["subdivisions", "9"]).unwrap(),
+            None
+        );
+        assert_eq!(
+            reader
+                .lookup_path(ip, &["country", "iso_code", "x"])
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            reader.lookup_path(IpAddr::from_str("10.0.0.1").unwrap(), &["country"]),
+            Err(Error::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_any() {
+        let buffer = std::fs::read("./testdata/GeoIP2-Anonymous-IP-Test.mmdb").unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(reader.get_metadata().database_type, "GeoIP2-Anonymous-IP");
+        let result = reader
+            .lookup(IpAddr::from_str("81.2.69.0").unwrap())
+            .unwrap();
+        assert_eq!(result.get("is_anonymous_vpn"), Some(&Value::Bool(true)));
+        let (network, result) = reader.networks().nth(1).unwrap().unwrap();
+        assert_eq!(network.to_string(), "1.2.0.0/16");
+        assert_eq!(result.get("is_anonymous"), Some(&Value::Bool(true)));
+
+        let reader = OwnedReader::<Any>::open("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let result = reader
+            .reader()
+            .lookup(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        assert_eq!(
+            result.get("city").unwrap().get("geoname_id"),
+            Some(&Value::Uint32(2643743))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let any = Reader::<Any>::from_bytes(&buffer).unwrap();
+        for ip in ["81.2.69.142", "202.196.224.0", "2a02:ff80::"] {
+            let ip = IpAddr::from_str(ip).unwrap();
+            assert_eq!(
+                serde_json::to_value(reader.lookup(ip).unwrap()).unwrap(),
+                serde_json::to_value(any.lookup(ip).unwrap()).unwrap()
+            );
+        }
+
+        let result = reader
+            .lookup(IpAddr::from_str("202.196.224.0").unwrap())
+            .unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["represented_country"]["type"], "military");
+        assert_eq!(json["represented_country"]["names"]["en"], "United States");
+        assert!(json.get("city").is_none());
+
+        let json = serde_json::to_value(reader.get_metadata()).unwrap();
+        assert_eq!(json["database_type"], "GeoIP2-City");
+        assert_eq!(json["languages"], serde_json::json!(["en", "zh"]));
+    }
+
+    #[test]
+    fn test_error() {
+        let err = Error::InvalidDataType {
+            expected: "map",
+            found: 2,
+            offset: 1234,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid data type at offset 1234: expected map, found string (2)"
+        );
+        assert_eq!(
+            Error::InvalidDatabaseType("GeoIP2-Anonymous-IP".into()).to_string(),
+            "unexpected database type \"GeoIP2-Anonymous-IP\""
+        );
+
+        let bytes = vec![0xff];
+        let err: Error = std::str::from_utf8(&bytes).unwrap_err().into();
+        assert!(std::error::Error::source(&err).is_some());
+
+        fn open() -> Result<(), Box<dyn std::error::Error>> {
+            let buffer = std::fs::read("./testdata/GeoIP2-Anonymous-IP-Test.mmdb")?;
+            Reader::<Country>::from_bytes(&buffer)?;
+            Ok(())
+        }
+        assert_eq!(
+            open().unwrap_err().to_string(),
+            "unexpected database type \"GeoIP2-Anonymous-IP\""
+        );
+    }
+
+    /// Builds an IPv4 database of a single node whose records both point to the
+    /// start of `data`, with `metadata` appended to the metadata map.
+    fn database(data: &[u8], metadata: &[(&str, &[u8])]) -> Vec<u8> {
+        database_with_tree(&[0, 0, 17, 0, 0, 17], 1, 4, data, metadata)
+    }
+
+    /// Builds a database of `tree`, made of `node_count` nodes of 24 bit
+    /// records, followed by `data`.
+    fn database_with_tree(
+        tree: &[u8],
+        node_count: u8,
+        ip_version: u8,
+        data: &[u8],
+        metadata: &[(&str, &[u8])],
+    ) -> Vec<u8> {
+        let mut buffer = tree.to_vec();
+        buffer.extend_from_slice(&[0; 16]);
+        buffer.extend_from_slice(data);
+        buffer.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        let node_count = [0xc1, node_count];
+        let ip_version = [0xa1, ip_version];
+        let mut entries = vec![
+            ("binary_format_major_version", &[0xa1, 2][..]),
+            ("node_count", &node_count[..]),
+            ("record_size", &[0xa1, 24][..]),
+            ("ip_version", &ip_version[..]),
+            ("database_type", &[0x44, b'T', b'e', b's', b't'][..]),
+            (
+                "description",
+                &[0xe1, 0x42, b'e', b'n', 0x44, b'T', b'e', b's', b't'][..],
+            ),
+        ];
+        entries.extend_from_slice(metadata);
+        buffer.push(0xe0 | entries.len() as u8);
+        for (key, value) in entries {
+            buffer.push(0x40 | key.len() as u8);
+            buffer.extend_from_slice(key.as_bytes());
+            buffer.extend_from_slice(value);
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_corrupt_database() {
+        fn exercise(buffer: &[u8]) {
+            let reader = match Reader::<City>::from_bytes(buffer) {
+                Ok(reader) => reader,
+                Err(_) => return,
+            };
+            for ip in ["81.2.69.142", "2.125.160.216", "2001:218::", "::"] {
+                let ip = IpAddr::from_str(ip).unwrap();
+                let _ = reader.lookup(ip);
+                let _ = reader.lookup_value(ip);
+            }
+            for result in reader.networks().take(64) {
+                let _ = result;
+            }
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        for len in (0..buffer.len()).step_by(97) {
+            exercise(&buffer[..len]);
+        }
+        let mut corrupt = buffer.clone();
+        for i in (0..buffer.len()).step_by(89) {
+            corrupt[i] ^= 0xff;
+            exercise(&corrupt);
+            corrupt[i] = buffer[i];
+        }
+
+        // A map holding a pointer to itself must not recurse forever.
+        let buffer = database(&[0xe1, 0x41, b'a', 0x20, 0x00], &[]);
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert!(matches!(
+            reader.lookup(IpAddr::from_str("1.1.1.1").unwrap()),
+            Err(Error::MaxDepthExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify() {
+        for entry in std::fs::read_dir("./testdata").unwrap() {
+            let buffer = std::fs::read(entry.unwrap().path()).unwrap();
+            let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+            assert_eq!(reader.verify(), Ok(()));
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let node_count = Reader::<City>::from_bytes(&buffer)
+            .unwrap()
+            .get_metadata()
+            .node_count as usize;
+        let search_tree_size = node_count * 7;
+        {
+            let mut buffer = buffer.clone();
+            buffer[search_tree_size + 3] = 1;
+            let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+            assert_eq!(reader.verify(), Err(Error::InvalidDataSectionSeparator));
+        }
+        {
+            // Point the last node's left record past the data section.
+            let mut buffer = buffer.clone();
+            buffer[search_tree_size - 7..search_tree_size - 4].fill(0xff);
+            buffer[search_tree_size - 4] |= 0xf0;
+            let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+            assert_eq!(reader.verify(), Err(Error::CorruptSearchTree));
+        }
+
+        // Without aliases IPv4-mapped addresses have a subtree of their own.
+        let mut writer = Writer::new("Test", 6).unwrap();
+        writer.ipv4_aliases = false;
+        for network in ["1.2.3.0/24", "::ffff:5.6.7.0/120"] {
+            writer
+                .insert(Network::from_str(network).unwrap(), Value::Bool(true))
+                .unwrap();
+        }
+        let buffer = writer.to_bytes().unwrap();
+        assert_eq!(Reader::<Any>::from_bytes(&buffer).unwrap().verify(), Ok(()));
+
+        // Strings are checked even with `unsafe-str`.
+        let buffer = database(&[0xe1, 0x41, b'a', 0x41, 0xff], &[]);
+        assert!(matches!(
+            Reader::<Any>::from_bytes(&buffer).unwrap().verify(),
+            Err(Error::Utf8Error(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_shared_nodes() {
+        // Both records of every node lead to the next one, 2^128 paths through
+        // only 128 nodes.
+        let mut tree = Vec::new();
+        for node in 1..=128u32 {
+            let record = if node == 128 { 128 + 16 } else { node };
+            tree.extend_from_slice(&record.to_be_bytes()[1..]);
+            tree.extend_from_slice(&record.to_be_bytes()[1..]);
+        }
+        let buffer = database_with_tree(&tree, 128, 6, &[0xe0], &[]);
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(reader.verify(), Err(Error::InvalidNode));
+        let results = reader.networks().collect::<Vec<_>>();
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 2);
+        assert!(results.contains(&Err(Error::InvalidNode)));
+    }
+
+    #[test]
+    fn test_map() {
+        #[derive(Default, Debug, Decoder)]
+        struct Record<'a> {
+            names: Option<geoip2::Map<'a>>,
+        }
+
+        // {"names": {"en": "London", "de": 1, "es": <pointer past the data section>}}
+        let buffer = database(
+            &[
+                0xe1, 0x45, b'n', b'a', b'm', b'e', b's', 0xe3, 0x42, b'e', b'n', 0x46, b'L', b'o',
+                b'n', b'd', b'o', b'n', 0x42, b'd', b'e', 0xa1, 0x01, 0x42, b'e', b's', 0x20, 0xff,
+            ],
+            &[],
+        );
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        // The entries are only decoded when used.
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("1.1.1.1").unwrap())
+            .unwrap();
+        let names = record.names.unwrap();
+        assert_eq!(names.get("en"), Ok(Some("London")));
+        assert!(matches!(
+            names.get("de"),
+            Err(Error::InvalidDataType { .. })
+        ));
+        let entries = names.iter().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1], Ok(("de", Value::Uint16(1))));
+        assert!(entries[2].is_err());
+    }
+
+    #[test]
+    fn test_data_types() {
+        let buffer = database(
+            &[
+                0xe4, 0x41, b'i', 0x04, 0x01, 0xff, 0xff, 0xff, 0xfe, 0x41, b'f', 0x04, 0x08, 0x3f,
+                0xc0, 0x00, 0x00, 0x41, b'u', 0x09, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x41, b'b', 0x83, 0x01, 0x02, 0x03,
+            ],
+            &[],
+        );
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(reader.verify(), Ok(()));
+        let value = reader.lookup(IpAddr::from_str("1.1.1.1").unwrap()).unwrap();
+        assert_eq!(value.get("i"), Some(&Value::Int32(-2)));
+        assert_eq!(value.get("f"), Some(&Value::Float(1.5)));
+        assert_eq!(value.get("u"), Some(&Value::Uint128(1 << 64)));
+        assert_eq!(value.get("b"), Some(&Value::Bytes(&[1, 2, 3])));
+
+        #[cfg(feature = "serde")]
+        {
+            #[derive(serde::Deserialize)]
+            struct Types<'a> {
+                i: i32,
+                f: f32,
+                u: u128,
+                b: &'a [u8],
+            }
+            let types: Types = reader
+                .lookup_as(IpAddr::from_str("1.1.1.1").unwrap())
+                .unwrap();
+            assert_eq!(types.i, -2);
+            assert_eq!(types.f, 1.5);
+            assert_eq!(types.u, 1 << 64);
+            assert_eq!(types.b, [1, 2, 3]);
+        }
+
+        // A node count that does not fit in a usize is an error, not truncated.
+        let buffer = database(
+            &[0xe0],
+            &[("node_count", &[0x09, 0x03, 1, 0, 0, 0, 0, 0, 0, 0, 0])],
+        );
+        assert!(matches!(
+            Reader::<Any>::from_bytes(&buffer).err(),
+            Some(Error::IntegerOverflow(_))
+        ));
+
+        // Nor is an ip_version of 0x10004, which used to be read as 4.
+        let buffer = database(&[0xe0], &[("ip_version", &[0xc3, 0x01, 0x00, 0x04])]);
+        assert!(matches!(
+            Reader::<Any>::from_bytes(&buffer).err(),
+            Some(Error::IntegerOverflow(_))
+        ));
+        // {"u": <uint16 of 3 bytes>}
+        let buffer = database(&[0xe1, 0x41, b'u', 0xa3, 0x01, 0x00, 0x00], &[]);
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(
+            reader.lookup(IpAddr::from_str("1.1.1.1").unwrap()),
+            Err(Error::IntegerOverflow(3))
+        );
+
+        #[derive(Default, Debug, Decoder)]
+        struct Unsigned {
+            u: Option<u64>,
+        }
+        // {"u": <uint64 of 8 bytes>}
+        let buffer = database(&[0xe1, 0x41, b'u', 0x08, 0x02, 1, 2, 3, 4, 5, 6, 7, 8], &[]);
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        let ip = IpAddr::from_str("1.1.1.1").unwrap();
+        let unsigned: Unsigned = reader.lookup_decoded(ip).unwrap();
+        assert_eq!(unsigned.u, Some(0x0102_0304_0506_0708));
+        // {"u": <uint128 of 9 bytes>}
+        let buffer = database(
+            &[0xe1, 0x41, b'u', 0x09, 0x03, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+            &[],
+        );
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(
+            reader.lookup_decoded::<Unsigned>(ip).unwrap_err(),
+            Error::IntegerOverflow(3)
+        );
+
+        // Doubles and floats have a fixed size, {"f": <double of 2 bytes>}
+        // and {"f": <float of 9 bytes>}.
+        for (data, data_type, size) in [
+            (&[0x62, 0x3f, 0xf0][..], 3, 2),
+            (&[0x09, 0x08, 0x3f, 0xc0, 0, 0, 0, 0, 0, 0, 0][..], 15, 9),
+        ] {
+            let buffer = database(&[&[0xe1, 0x41, b'f'][..], data].concat(), &[]);
+            let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+            let err = || Error::InvalidDataSize {
+                data_type,
+                size,
+                offset: 3,
+            };
+            let ip = IpAddr::from_str("1.1.1.1").unwrap();
+            assert_eq!(reader.verify(), Err(err()));
+            assert_eq!(reader.lookup(ip), Err(err()));
+            #[cfg(feature = "serde")]
+            assert_eq!(
+                reader.lookup_as::<std::collections::HashMap<&str, f64>>(ip),
+                Err(err())
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        // {"domain": "a.com", "new": {"x": [1, true, "y"]}, "alias": <pointer to 0>}
+        let buffer = database(
+            &[
+                0xe3, 0x46, b'd', b'o', b'm', b'a', b'i', b'n', 0x45, b'a', b'.', b'c', b'o', b'm',
+                0x43, b'n', b'e', b'w', 0xe1, 0x41, b'x', 0x03, 0x04, 0xa1, 0x01, 0x01, 0x07, 0x41,
+                b'y', 0x45, b'a', b'l', b'i', b'a', b's', 0x20, 0x00,
+            ],
+            &[(
+                "database_type",
+                &[
+                    0x4d, b'G', b'e', b'o', b'I', b'P', b'2', b'-', b'D', b'o', b'm', b'a', b'i',
+                    b'n',
+                ],
+            )],
+        );
+        let reader = Reader::<Domain>::from_bytes(&buffer).unwrap();
+        let ip = IpAddr::from_str("1.1.1.1").unwrap();
+        assert_eq!(reader.lookup(ip).unwrap().domain, Some("a.com"));
+
+        #[derive(Default, Debug, Decoder)]
+        #[geoip2(deny_unknown_fields)]
+        struct StrictDomain<'a> {
+            domain: Option<&'a str>,
+        }
+        assert_eq!(
+            reader.lookup_decoded::<StrictDomain>(ip).unwrap_err(),
+            Error::UnknownField("new".into())
+        );
+    }
+
+    #[test]
+    fn test_derive() {
+        #[derive(Default, Debug, Decoder)]
+        struct Place<'a> {
+            #[geoip2(rename = "iso_code")]
+            code: Option<&'a str>,
+            #[geoip2(rename = "type")]
+            kind: Option<&'a str>,
+            names: Option<geoip2::Map<'a>>,
+        }
+
+        #[derive(Default, Debug, Decoder)]
+        #[cfg_attr(feature = "serde", geoip2(serialize))]
+        struct Location {
+            latitude: Option<f64>,
+            longitude: Option<f64>,
+        }
+
+        #[derive(Default, Debug, Decoder)]
+        struct Record<'a> {
+            country: Option<Place<'a>>,
+            represented_country: Option<Place<'a>>,
+            subdivisions: Option<Vec<Place<'a>>>,
+            location: Option<Location>,
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("81.2.69.142").unwrap())
+            .unwrap();
+        let country = record.country.unwrap();
+        assert_eq!(country.code, Some("GB"));
+        assert_eq!(country.names.unwrap().get("en"), Ok(Some("United Kingdom")));
+        assert_eq!(record.subdivisions.unwrap()[0].code, Some("ENG"));
+        assert_eq!(record.location.unwrap().latitude, Some(51.5142));
+
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("202.196.224.0").unwrap())
+            .unwrap();
+        assert_eq!(record.represented_country.unwrap().kind, Some("military"));
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_string(&record.location.unwrap()).unwrap(),
+            r#"{"latitude":13.0,"longitude":122.0}"#
+        );
+    }
+
+    #[test]
+    fn test_derive_attributes() {
+        #[derive(Default, Debug, Decoder)]
+        #[cfg_attr(feature = "serde", geoip2(serialize))]
+        struct Location {
+            latitude: Option<f64>,
+            #[geoip2(skip)]
+            longitude: Option<f64>,
+        }
+
+        #[derive(Default, Debug, Decoder)]
+        #[cfg_attr(feature = "serde", geoip2(serialize))]
+        struct Postal<'a> {
+            code: Option<&'a str>,
+        }
+
+        #[derive(Default, Debug, Decoder)]
+        #[cfg_attr(feature = "serde", geoip2(serialize))]
+        struct Geo<'a> {
+            location: Option<Location>,
+            postal: Option<Postal<'a>>,
+        }
+
+        #[derive(Default, Debug, Decoder)]
+        #[cfg_attr(feature = "serde", geoip2(serialize))]
+        struct Record<'a> {
+            #[geoip2(flatten)]
+            geo: Geo<'a>,
+            #[geoip2(rename = "city", skip)]
+            skipped: Option<Postal<'a>>,
+        }
+
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("2.125.160.216").unwrap())
+            .unwrap();
+        assert!(record.skipped.is_none());
+        assert_eq!(record.geo.postal.as_ref().unwrap().code, Some("OX1"));
+        let location = record.geo.location.as_ref().unwrap();
+        assert_eq!(location.latitude, Some(51.75));
+        assert_eq!(location.longitude, None);
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"location":{"latitude":51.75},"postal":{"code":"OX1"}}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_derive_with_serde() {
+        // Without #[geoip2(serialize)] the derive leaves Serialize to serde.
+        #[derive(Default, Debug, Decoder, serde::Serialize)]
+        struct Record<'a> {
+            a: Option<&'a str>,
+        }
+
+        let buffer = database(&[0xe1, 0x41, b'a', 0x41, b'b'], &[]);
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("1.1.1.1").unwrap())
+            .unwrap();
+        assert_eq!(serde_json::to_string(&record).unwrap(), r#"{"a":"b"}"#);
+    }
+
+    #[test]
+    fn test_derive_types() {
+        #[derive(Default, Debug, Decoder)]
+        struct Record<'a> {
+            ids: Vec<u32>,
+            tags: Option<Vec<&'a str>>,
+            grid: Option<Vec<Vec<u16>>>,
+            score: i32,
+        }
+
+        // {"ids": [1, 2], "tags": ["a", "b"], "grid": [[1], [2, 3]], "score": -1}
+        let buffer = database(
+            &[
+                0xe4, 0x43, b'i', b'd', b's', 0x02, 0x04, 0xc1, 0x01, 0xc1, 0x02, 0x44, b't', b'a',
+                b'g', b's', 0x02, 0x04, 0x41, b'a', 0x41, b'b', 0x44, b'g', b'r', b'i', b'd', 0x02,
+                0x04, 0x01, 0x04, 0xa1, 0x01, 0x02, 0x04, 0xa1, 0x02, 0xa1, 0x03, 0x45, b's', b'c',
+                b'o', b'r', b'e', 0x04, 0x01, 0xff, 0xff, 0xff, 0xff,
+            ],
+            &[],
+        );
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        let record: Record = reader
+            .lookup_decoded(IpAddr::from_str("1.1.1.1").unwrap())
+            .unwrap();
+        assert_eq!(record.ids, [1, 2]);
+        assert_eq!(record.tags, Some(vec!["a", "b"]));
+        assert_eq!(record.grid, Some(vec![vec![1], vec![2, 3]]));
+        assert_eq!(record.score, -1);
+    }
+
+    #[test]
+    fn test_writer() {
+        let names = Value::Map(vec![
+            ("en", Value::String("London")),
+            ("es", Value::String("Londres")),
+        ]);
+        let london = Value::Map(vec![
+            ("city", Value::Map(vec![("names", names.clone())])),
+            (
+                "location",
+                Value::Map(vec![
+                    ("latitude", Value::Double(51.5142)),
+                    ("accuracy_radius", Value::Uint16(10)),
+                ]),
+            ),
+        ]);
+        let office = Value::Map(vec![
+            ("city", Value::Map(vec![("names", names)])),
+            ("office", Value::Bool(true)),
+            (
+                "floors",
+                Value::Array(vec![Value::Int32(-1), Value::Int32(2)]),
+            ),
+        ]);
+        for ip_version in [4, 6] {
+            for record_size in [24, 28, 32] {
+                let mut writer = Writer::new("GeoIP2-City", ip_version).unwrap();
+                writer.record_size = record_size;
+                writer.languages = vec!["en", "es"];
+                writer.description = vec![("en", "Test database")];
+                writer.build_epoch = 1_700_000_000;
+                writer
+                    .insert(Network::from_str("81.2.69.0/24").unwrap(), london.clone())
+                    .unwrap();
+                writer
+                    .insert(Network::from_str("81.2.69.128/26").unwrap(), office.clone())
+                    .unwrap();
+                if ip_version == 6 {
+                    writer
+                        .insert(Network::from_str("2a02:ff80::/29").unwrap(), london.clone())
+                        .unwrap();
+                } else {
+                    assert_eq!(
+                        writer.insert(Network::from_str("2a02:ff80::/29").unwrap(), london.clone()),
+                        Err(Error::IPv4Only)
+                    );
+                }
+                let buffer = writer.to_bytes().unwrap();
+                // The names are written once and pointed to from the office.
+                assert_eq!(buffer.windows(7).filter(|v| v == b"Londres").count(), 1);
+
+                let reader = Reader::<City>::from_bytes(&buffer).unwrap();
+                reader.verify().unwrap();
+                let metadata = reader.get_metadata();
+                assert_eq!(metadata.ip_version, ip_version);
+                assert_eq!(metadata.record_size, record_size);
+                assert_eq!(metadata.languages, vec!["en", "es"]);
+                assert_eq!(metadata.description.get("en"), Ok(Some("Test database")));
+                assert_eq!(metadata.build_epoch, 1_700_000_000);
+
+                let ip = IpAddr::from_str("81.2.69.1").unwrap();
+                assert_eq!(reader.lookup_value(ip).unwrap(), london);
+                let result = reader.lookup(ip).unwrap();
+                assert_eq!(
+                    result.city.unwrap().names.unwrap().get("es"),
+                    Ok(Some("Londres"))
+                );
+                assert_eq!(result.location.unwrap().accuracy_radius, Some(10));
+                let ip = IpAddr::from_str("81.2.69.142").unwrap();
+                assert_eq!(reader.lookup_value(ip).unwrap(), office);
+                assert_eq!(
+                    reader
+                        .lookup_network(IpAddr::from_str("81.2.69.200").unwrap())
+                        .unwrap()
+                        .1,
+                    Network::from_str("81.2.69.192/26").unwrap()
+                );
+                assert_eq!(
+                    reader
+                        .lookup(IpAddr::from_str("81.2.70.1").unwrap())
+                        .unwrap_err(),
+                    Error::NotFound
+                );
+                let networks = Reader::<Any>::from_bytes(&buffer)
+                    .unwrap()
+                    .networks()
+                    .map(|result| result.unwrap().0.to_string())
+                    .collect::<Vec<_>>();
+                let mut expected = vec!["81.2.69.0/25", "81.2.69.128/26", "81.2.69.192/26"];
+                if ip_version == 6 {
+                    expected.push("2a02:ff80::/29");
+                }
+                assert_eq!(networks, expected);
+            }
+        }
+
+        let mut writer = Writer::new("Test", 4).unwrap();
+        writer.record_size = 30;
+        assert_eq!(writer.to_bytes(), Err(Error::InvalidRecordSize(30)));
+        assert!(Writer::new("Test", 5).is_err());
+        writer.record_size = 24;
+        writer.description.clear();
+        assert_eq!(
+            writer.to_bytes(),
+            Err(Error::InvalidMetadataField("description"))
+        );
+        writer.description = vec![("en", "Test")];
+        // An empty database still has a root node.
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        reader.verify().unwrap();
+        assert_eq!(reader.get_metadata().node_count, 1);
+
+        // The largest size a control byte can hold.
+        let bytes = vec![0; 65_821 + 0xff_ffff + 1];
+        let mut writer = Writer::new("Test", 4).unwrap();
+        let network = Network::from_str("1.0.0.0/8").unwrap();
+        assert_eq!(
+            writer.insert(network, Value::Bytes(&bytes)),
+            Err(Error::DataTooLarge {
+                data_type: 4,
+                size: bytes.len()
+            })
+        );
+        writer.insert(network, Value::Bytes(&bytes[1..])).unwrap();
+        // Past the 2^24 offsets 24 bit records can point to.
+        writer.record_size = 24;
+        writer
+            .insert(
+                Network::from_str("2.0.0.0/8").unwrap(),
+                Value::Bytes(&bytes[2..]),
+            )
+            .unwrap();
+        assert_eq!(writer.to_bytes().err(), Some(Error::RecordSizeTooSmall(24)));
+        writer.record_size = 28;
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        assert_eq!(
+            reader.lookup_value(IpAddr::from_str("2.2.2.2").unwrap()),
+            Ok(Value::Bytes(&bytes[2..]))
+        );
+    }
+
+    #[test]
+    fn test_writer_strategies() {
+        let site = |name| Value::Map(vec![("name", Value::String(name))]);
+        let mut writer = Writer::new("Test", 4).unwrap();
+        writer.include_reserved_networks = true;
+        let network = |network| Network::from_str(network).unwrap();
+        writer
+            .insert(
+                network("10.0.0.0/8"),
+                Value::Map(vec![
+                    ("office", Value::Bool(true)),
+                    (
+                        "site",
+                        Value::Map(vec![
+                            ("name", Value::String("hq")),
+                            ("floor", Value::Uint16(1)),
+                        ]),
+                    ),
+                ]),
+            )
+            .unwrap();
+        let value = Value::Map(vec![("site", site("ams"))]);
+        for (network_str, strategy) in [
+            ("10.1.0.0/16", InsertStrategy::ShallowMerge),
+            ("10.2.0.0/16", InsertStrategy::DeepMerge),
+            ("10.3.0.0/16", InsertStrategy::Replace),
+            ("11.0.0.0/8", InsertStrategy::DeepMerge),
+        ] {
+            writer
+                .insert_with(network(network_str), value.clone(), strategy)
+                .unwrap();
+        }
+        // Merged into every record under 10.0.0.0/8.
+        writer
+            .insert_with(
+                network("10.0.0.0/8"),
+                Value::Map(vec![("country", Value::String("NL"))]),
+                InsertStrategy::ShallowMerge,
+            )
+            .unwrap();
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        reader.verify().unwrap();
+        let lookup = |ip| reader.lookup(IpAddr::from_str(ip).unwrap()).unwrap();
+        let country = ("country", Value::String("NL"));
+        assert_eq!(
+            lookup("10.0.0.1"),
+            Value::Map(vec![
+                ("office", Value::Bool(true)),
+                (
+                    "site",
+                    Value::Map(vec![
+                        ("name", Value::String("hq")),
+                        ("floor", Value::Uint16(1)),
+                    ])
+                ),
+                country.clone(),
+            ])
+        );
+        assert_eq!(
+            lookup("10.1.0.1"),
+            Value::Map(vec![
+                ("office", Value::Bool(true)),
+                ("site", site("ams")),
+                country.clone(),
+            ])
+        );
+        assert_eq!(
+            lookup("10.2.0.1"),
+            Value::Map(vec![
+                ("office", Value::Bool(true)),
+                (
+                    "site",
+                    Value::Map(vec![
+                        ("name", Value::String("ams")),
+                        ("floor", Value::Uint16(1)),
+                    ])
+                ),
+                country.clone(),
+            ])
+        );
+        assert_eq!(
+            lookup("10.3.0.1"),
+            Value::Map(vec![("site", site("ams")), country])
+        );
+        assert_eq!(lookup("11.0.0.1"), value);
+    }
+
+    #[test]
+    fn test_writer_reserved_networks() {
+        let value = Value::Map(vec![("found", Value::Bool(true))]);
+        let mut writer = Writer::new("Test", 6).unwrap();
+        assert_eq!(
+            writer.insert(Network::from_str("10.1.0.0/16").unwrap(), value.clone()),
+            Err(Error::ReservedNetwork(
+                Network::from_str("10.1.0.0/16").unwrap()
+            ))
+        );
+        assert!(writer
+            .insert(Network::from_str("2002:a00::/24").unwrap(), value.clone())
+            .is_err());
+        writer
+            .insert(Network::from_str("::/0").unwrap(), value.clone())
+            .unwrap();
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        reader.verify().unwrap();
+        for ip in [
+            "8.8.8.8",
+            "2a02:ff80::1",
+            "::ffff:8.8.8.8",
+            "2001:0:808:808::",
+            "2002:808:808::",
+        ] {
+            assert_eq!(
+                reader.lookup(IpAddr::from_str(ip).unwrap()),
+                Ok(value.clone())
+            );
+        }
+        for ip in [
+            "10.1.1.1",
+            "192.168.1.1",
+            "2001:db8::1",
+            "fe80::1",
+            "::ffff:10.1.1.1",
+            "2001:0:a01:101::",
+            "2001:1::1",
+            "2002:a01:101::",
+        ] {
+            assert_eq!(
+                reader.lookup(IpAddr::from_str(ip).unwrap()),
+                Err(Error::NotFound)
+            );
+        }
+
+        // The IPv4 subtree is a branch, so the aliases point to its node.
+        writer
+            .insert(
+                Network::from_str("81.2.69.0/24").unwrap(),
+                Value::Bool(true),
+            )
+            .unwrap();
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        reader.verify().unwrap();
+        for ip in [
+            "81.2.69.1",
+            "::ffff:81.2.69.1",
+            "2001:0:5102:4501::",
+            "2002:5102:4501::",
+        ] {
+            assert_eq!(
+                reader.lookup(IpAddr::from_str(ip).unwrap()),
+                Ok(Value::Bool(true))
+            );
+        }
+        // Networks under the aliases are only listed once.
+        let network = Network::from_str("81.2.69.0/24").unwrap();
+        assert_eq!(
+            reader
+                .networks()
+                .filter(|result| result.as_ref().unwrap().0 == network)
+                .count(),
+            1
+        );
+
+        writer.include_reserved_networks = true;
+        writer.ipv4_aliases = false;
+        writer
+            .insert(Network::from_str("10.1.0.0/16").unwrap(), value.clone())
+            .unwrap();
+        let buffer = writer.to_bytes().unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        reader.verify().unwrap();
+        assert_eq!(
+            reader.lookup(IpAddr::from_str("10.1.1.1").unwrap()),
+            Ok(value.clone())
+        );
+        assert_eq!(
+            reader.lookup(IpAddr::from_str("::ffff:81.2.69.1").unwrap()),
+            Ok(value.clone())
+        );
+
+        // Without aliases Teredo is a network like any other, unlike the rest
+        // of 2001::/23.
+        let mut writer = Writer::new("Test", 6).unwrap();
+        writer.ipv4_aliases = false;
+        assert!(writer
+            .insert(Network::from_str("2001::/32").unwrap(), value.clone())
+            .is_ok());
+        assert!(writer
+            .insert(Network::from_str("2001:1::/32").unwrap(), value)
+            .is_err());
+    }
+
+    #[test]
+    fn test_writer_round_trip() {
+        let buffer = std::fs::read("./testdata/GeoIP2-City-Test.mmdb").unwrap();
+        let reader = Reader::<Any>::from_bytes(&buffer).unwrap();
+        let mut writer = Writer::new("GeoIP2-City", 6).unwrap();
+        let networks = reader.networks().collect::<Result<Vec<_>, _>>().unwrap();
+        for (network, value) in &networks {
+            writer.insert(*network, value.clone()).unwrap();
+        }
+        let written = writer.to_bytes().unwrap();
+        assert!(written.len() < buffer.len());
+        let rewritten = Reader::<Any>::from_bytes(&written).unwrap();
+        rewritten.verify().unwrap();
+        assert_eq!(
+            rewritten.get_metadata().description.get("en"),
+            Ok(Some("GeoIP2-City"))
+        );
+        maxminddb::Reader::from_source(&written[..])
+            .unwrap()
+            .verify()
+            .unwrap();
+        for (network, value) in &networks {
+            assert_eq!(&rewritten.lookup(network.address()).unwrap(), value);
+        }
+        assert_eq!(
+            rewritten.networks().collect::<Result<Vec<_>, _>>().unwrap(),
+            networks
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_cli() {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let run = |args: &[&str], stdin: &str| {
+            let mut child = Command::new(env!("CARGO_BIN_EXE_geoip2"))
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap();
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(stdin.as_bytes())
+                .unwrap();
+            let output = child.wait_with_output().unwrap();
+            (
+                output.status.code(),
+                String::from_utf8(output.stdout).unwrap(),
+            )
+        };
+        let db = "./testdata/GeoIP2-City-Test.mmdb";
+
+        let (code, stdout) = run(&["lookup", db, "81.2.69.142", "10.0.0.1"], "");
+        assert_eq!(code, Some(0));
+        let lines = stdout
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["network"], "81.2.69.142/31");
+        assert_eq!(lines[0]["data"]["city"]["names"]["en"], "London");
+        assert_eq!(lines[1]["data"], serde_json::Value::Null);
+
+        let (code, stdout) = run(&["lookup", db], "89.160.20.120\n\ninvalid\n");
+        assert_eq!(code, Some(1));
+        let line = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+        assert_eq!(line["network"], "89.160.20.112/28");
+
+        let (code, stdout) = run(&["metadata", db], "");
+        assert_eq!(code, Some(0));
+        let metadata = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+        assert_eq!(metadata["database_type"], "GeoIP2-City");
+
+        assert_eq!(run(&["verify", db], ""), (Some(0), "ok\n".into()));
+
+        let (code, stdout) = run(&["dump", db], "");
+        assert_eq!(code, Some(0));
+        let (_, stats) = run(&["stats", db], "");
+        let stats = serde_json::from_str::<serde_json::Value>(&stats).unwrap();
+        assert_eq!(stats["networks"], stdout.lines().count());
+
+        assert_eq!(run(&["verify"], "").0, Some(2));
+        assert_eq!(run(&["verify", "./testdata/missing.mmdb"], "").0, Some(1));
+        // Usage errors are reported before the database is opened.
+        assert_eq!(run(&["unknown", "./testdata/missing.mmdb"], "").0, Some(2));
+        assert_eq!(run(&["verify", db, "1.1.1.1"], "").0, Some(2));
+    }
+
     #[test]
     fn test_connection_type() {
         let buffer = std::fs::read("./testdata/GeoIP2-Connection-Type-Test.mmdb").unwrap();
@@ -180,15 +1601,15 @@ mod tests {
             assert_eq!(continent.geoname_id, Some(6255149));
             assert_eq!(continent.code, Some("NA"));
             let names = continent.names.unwrap();
-            assert_eq!(names.get("es"), Some("Norteamérica"));
-            assert_eq!(names.get("ru"), Some("Северная Америка"));
+            assert_eq!(names.get("es"), Ok(Some("Norteamérica")));
+            assert_eq!(names.get("ru"), Ok(Some("Северная Америка")));
 
             let country = result.country.unwrap();
             assert_eq!(country.geoname_id, Some(6252001));
             assert_eq!(country.iso_code, Some("US"));
             let names = country.names.unwrap();
-            assert_eq!(names.get("fr"), Some("États-Unis"));
-            assert_eq!(names.get("pt-BR"), Some("Estados Unidos"));
+            assert_eq!(names.get("fr"), Ok(Some("États-Unis")));
+            assert_eq!(names.get("pt-BR"), Ok(Some("Estados Unidos")));
             assert_eq!(country.is_in_european_union, None);
 
             let registered_country = result.registered_country.unwrap();
@@ -208,15 +1629,15 @@ mod tests {
             assert_eq!(continent.geoname_id, Some(6255148));
             assert_eq!(continent.code, Some("EU"));
             let names = continent.names.unwrap();
-            assert_eq!(names.get("en"), Some("Europe"));
-            assert_eq!(names.get("zh-CN"), Some("欧洲"));
+            assert_eq!(names.get("en"), Ok(Some("Europe")));
+            assert_eq!(names.get("zh-CN"), Ok(Some("欧洲")));
 
             let country = result.country.unwrap();
             assert_eq!(country.geoname_id, Some(2411586));
             assert_eq!(country.iso_code, Some("GI"));
             let names = country.names.unwrap();
-            assert_eq!(names.get("en"), Some("Gibraltar"));
-            assert_eq!(names.get("ja"), Some("ジブラルタル"));
+            assert_eq!(names.get("en"), Ok(Some("Gibraltar")));
+            assert_eq!(names.get("ja"), Ok(Some("ジブラルタル")));
             assert_eq!(country.is_in_european_union, None);
 
             let registered_country = result.registered_country.unwrap();
@@ -0,0 +1,10 @@
+use geoip2::Decoder;
+
+#[derive(Default, Decoder)]
+enum Kind {
+    #[default]
+    Country,
+    City,
+}
+
+fn main() {}
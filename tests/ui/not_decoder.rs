@@ -0,0 +1,13 @@
+use geoip2::Decoder;
+
+#[derive(Default)]
+struct Location {
+    latitude: f64,
+}
+
+#[derive(Default, Decoder)]
+struct Record {
+    location: Option<Location>,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use geoip2::Decoder;
+
+#[derive(Default, Decoder)]
+struct Code<'a>(&'a str);
+
+fn main() {}
@@ -0,0 +1,10 @@
+use geoip2::Decoder;
+
+#[derive(Default, Decoder)]
+struct Record {
+    geoname_id: Option<i64>,
+    accuracy_radius: usize,
+    name: String,
+}
+
+fn main() {}